@@ -0,0 +1,109 @@
+/*
+
+Counting allocations
+
+Whether a piece of code moves or clones is easiest to check by counting how many
+times it asks the allocator for memory. A move asks for nothing; a clone of a
+String asks for one new heap block.
+
+This module installs a global allocator that passes every request on to the
+system allocator and counts it on the way through. Frees are counted as well, so
+a test can also see when memory is handed back. It is only built with the
+count-allocations feature, since it replaces the allocator for the whole program.
+
+Tests that count allocations are behind the same feature, so run them with
+
+    cargo test --features count-allocations
+
+*/
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    // per thread, so allocations made by other threads don't show up in a count
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static FREES: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAlloc;
+
+impl CountingAlloc {
+    fn record() {
+        // try_with, because a thread can still allocate while it's locals are torn down
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+    }
+
+    fn record_free() {
+        let _ = FREES.try_with(|n| n.set(n.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::record();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::record();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::record();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        Self::record_free();
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f` and returns it's result along with how many allocations it made on
+/// this thread. Growing an existing block (a realloc) counts as one too.
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    let after = ALLOCATIONS.with(Cell::get);
+    (result, after - before)
+}
+
+/// How many heap blocks this thread has handed back so far. Compare two readings
+/// to see whether, and when, something was freed.
+pub fn frees_so_far() -> usize {
+    FREES.with(Cell::get)
+}
+
+#[cfg(all(test, feature = "count-allocations"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_new_string_allocates() {
+        let (s, n) = count_allocations(|| String::from("x"));
+        assert_eq!(s, "x");
+        assert!(n >= 1);
+    }
+
+    #[test]
+    fn plain_numbers_allocate_nothing() {
+        assert_eq!(count_allocations(|| 5), (5, 0));
+    }
+
+    #[test]
+    fn dropping_a_string_frees_it_once() {
+        let s = String::from("x");
+        let before = frees_so_far();
+        drop(s);
+        assert_eq!(frees_so_far(), before + 1);
+
+        let empty = String::new();
+        drop(empty);
+        assert_eq!(frees_so_far(), before + 1, "an empty String owns no block");
+    }
+}
@@ -0,0 +1,230 @@
+/*
+
+Looking at text without copying it
+
+These functions only read their input through a borrow and return plain numbers,
+so they never allocate. All but shannon_entropy stick to what's in core, and would
+work just as well in a no_std build; that one needs std for the logarithm.
+
+*/
+
+use core::cmp::Ordering;
+
+/// Counts how many times each byte value occurs in `s`.
+///
+/// Index the result with a byte: `byte_histogram(s)[b'a' as usize]`. Multibyte
+/// chars are counted as the UTF-8 bytes they're made of.
+pub fn byte_histogram(s: &str) -> [u32; 256] {
+    let mut counts = [0u32; 256];
+    for &b in s.as_bytes() {
+        counts[b as usize] += 1;
+    }
+    counts
+}
+
+/// Checks two strings for equality, returning early when their byte lengths differ.
+///
+/// `==` on &str already does this; spelling it out shows that the length check
+/// alone settles most unequal pairs without looking at a single byte of content.
+pub fn fast_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.as_bytes() == b.as_bytes()
+}
+
+/// Orders two strings by byte length first, then by their bytes.
+///
+/// This is not dictionary order: "b" comes before "aa" because it is shorter.
+/// In exchange, strings of different lengths are ordered without reading them.
+pub fn fast_cmp(a: &str, b: &str) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.as_bytes().cmp(b.as_bytes()))
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes the UTF-8 bytes of `s` with 64-bit FNV-1a.
+///
+/// HashMap's default hasher is seeded randomly for each run, so it's hashes can't
+/// be stored or compared between runs. This one always gives the same value for
+/// the same bytes, on every platform.
+pub fn stable_hash(s: &str) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for &b in s.as_bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Checks whether `s` reads the same forwards and backwards, looking only at
+/// letters and digits and ignoring ASCII case.
+///
+/// Chars are taken from both ends at once, so no reversed copy is ever built.
+pub fn is_palindrome(s: &str) -> bool {
+    let mut chars = s.chars().filter(|c| c.is_alphanumeric());
+    while let (Some(front), Some(back)) = (chars.next(), chars.next_back()) {
+        if !front.eq_ignore_ascii_case(&back) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Measures how unpredictable the bytes of `s` are, in bits per byte.
+///
+/// Text made of one repeated byte scores 0.0, two bytes in equal amounts score
+/// 1.0, and the highest possible score is 8.0. An empty string scores 0.0. This
+/// is the one function here that needs std, for `f64::log2`.
+pub fn shannon_entropy(s: &str) -> f64 {
+    if s.is_empty() {
+        return 0.0;
+    }
+
+    let total = s.len() as f64;
+    byte_histogram(s)
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = f64::from(count) / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Counts how many different ASCII chars occur in `s`. Other chars are skipped.
+///
+/// There are only 128 ASCII chars, so which ones have been seen fits in two u64s
+/// used as a bitset, one bit per char, instead of a HashSet.
+pub fn distinct_ascii_chars(s: &str) -> u32 {
+    let mut seen = [0u64; 2];
+    for b in s.bytes().filter(u8::is_ascii) {
+        seen[(b / 64) as usize] |= 1 << (b % 64);
+    }
+    seen[0].count_ones() + seen[1].count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_histogram_of_repeated_a() {
+        let counts = byte_histogram("aaaa");
+        assert_eq!(counts[b'a' as usize], 4);
+        assert_eq!(counts.iter().sum::<u32>(), 4);
+    }
+
+    #[test]
+    fn byte_histogram_counts_each_utf8_byte() {
+        let counts = byte_histogram("é"); // C3 A9
+        assert_eq!(counts[0xC3], 1);
+        assert_eq!(counts[0xA9], 1);
+        assert_eq!(counts.iter().sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn byte_histogram_of_nothing_is_all_zero() {
+        assert!(byte_histogram("").iter().all(|&n| n == 0));
+    }
+
+    #[test]
+    fn fast_eq_and_cmp_of_equal_strings() {
+        assert!(fast_eq("héllo", "héllo"));
+        assert_eq!(fast_cmp("héllo", "héllo"), Ordering::Equal);
+    }
+
+    #[test]
+    fn fast_eq_and_cmp_of_different_lengths() {
+        assert!(!fast_eq("ab", "abc"));
+        assert_eq!(fast_cmp("b", "aa"), Ordering::Less); // shorter first, not dictionary order
+        assert_eq!(fast_cmp("aa", "b"), Ordering::Greater);
+    }
+
+    #[test]
+    fn fast_eq_and_cmp_of_same_length_different_bytes() {
+        assert!(!fast_eq("abc", "abd"));
+        assert_eq!(fast_cmp("abc", "abd"), Ordering::Less);
+        assert_eq!(fast_cmp("abd", "abc"), Ordering::Greater);
+    }
+
+    #[test]
+    fn stable_hash_matches_fnv1a_vectors() {
+        assert_eq!(stable_hash("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(stable_hash("foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn stable_hash_of_nothing_is_the_offset_basis() {
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+    }
+
+    #[test]
+    fn stable_hash_hashes_utf8_bytes() {
+        // FNV-1a of the bytes C3 A9
+        assert_eq!(stable_hash("é"), 0x0ac21707b7181e01);
+        assert_ne!(stable_hash("é"), stable_hash("e"));
+    }
+
+    #[test]
+    fn is_palindrome_ignores_punctuation_and_case() {
+        assert!(is_palindrome("A man, a plan, a canal: Panama"));
+        assert!(is_palindrome(""));
+        assert!(is_palindrome("x"));
+    }
+
+    #[test]
+    fn is_palindrome_of_multibyte_chars() {
+        assert!(is_palindrome("été"));
+        assert!(is_palindrome("日本日"));
+        assert!(!is_palindrome("éte"));
+    }
+
+    #[test]
+    fn is_palindrome_rejects_a_non_palindrome() {
+        assert!(!is_palindrome("hello"));
+        assert!(!is_palindrome("ab, c"));
+    }
+
+    #[test]
+    fn shannon_entropy_of_nothing_or_one_byte_is_zero() {
+        assert_eq!(shannon_entropy(""), 0.0);
+        assert_eq!(shannon_entropy("a"), 0.0);
+        assert_eq!(shannon_entropy("aaaa"), 0.0);
+    }
+
+    #[test]
+    fn shannon_entropy_of_two_equal_bytes_is_one_bit() {
+        assert!((shannon_entropy("ab") - 1.0).abs() < 1e-12);
+        assert!((shannon_entropy("abab") - 1.0).abs() < 1e-12);
+        // four equally common bytes make two bits
+        assert!((shannon_entropy("abcd") - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn distinct_ascii_chars_counts_repeats_once() {
+        assert_eq!(distinct_ascii_chars("hello"), 4);
+    }
+
+    #[test]
+    fn distinct_ascii_chars_of_nothing() {
+        assert_eq!(distinct_ascii_chars(""), 0);
+    }
+
+    #[test]
+    fn distinct_ascii_chars_skips_other_chars() {
+        assert_eq!(distinct_ascii_chars("éàü"), 0);
+        assert_eq!(distinct_ascii_chars("aé a"), 2);
+    }
+
+    #[test]
+    fn distinct_ascii_chars_uses_both_halves_of_the_bitset() {
+        // '\0' and '?' are below 64, '@' and DEL (127) above
+        assert_eq!(distinct_ascii_chars("\0?@\u{7f}"), 4);
+        let all: String = (0..128u8).map(char::from).collect();
+        assert_eq!(distinct_ascii_chars(&all), 128);
+    }
+}
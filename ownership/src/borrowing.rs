@@ -0,0 +1,534 @@
+/*
+
+Borrowing out of owned data
+
+A struct that owns its data can lend parts of it out. The reference it hands back
+is tied to the borrow of the struct itself: while we hold a &str we got from
+config.get(..), config can't be changed, because that could free or move the
+String the &str points into.
+
+*/
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::parse::sentence_spans;
+
+/// Owned key/value settings that lend their values out as &str.
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the returned &str lives as long as the &self borrow, no longer
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the value for `key`, first storing an owned copy of `default` if
+    /// the key is missing. An existing value is never overwritten.
+    pub fn get_or_insert(&mut self, key: &str, default: &str) -> &str {
+        if !self.values.contains_key(key) {
+            self.values.insert(key.to_string(), default.to_string());
+        }
+
+        // the unique borrow of self is downgraded to the shared one we return
+        &self.values[key]
+    }
+}
+
+/*
+
+Naming the borrow kinds
+
+`&x` is a shared borrow and `&mut x` is a unique one. The two macros below do
+nothing more than take these borrows, but spelling out which kind we want makes the
+aliasing rules easy to point at: any number of shared borrows may be live at once,
+or exactly one unique borrow, never both. The compiler checks this for us, so a
+borrow the macros hand back is always a legal one.
+
+    let mut s = String::from("hello");
+
+    let r1 = shared_borrow!(s);
+    let r2 = shared_borrow!(s); // fine, shared borrows can overlap
+    println!("{r1} {r2}");
+
+    let r3 = unique_borrow!(s); // fine, r1 and r2 are no longer used
+    r3.push_str(", world");
+
+Taking a unique borrow while a shared one is still in use won't compile:
+
+    let r1 = shared_borrow!(s);
+    // let r2 = unique_borrow!(s);
+    // println!("{r1} {r2}");
+
+    error[E0502]: cannot borrow `s` as mutable because it is also borrowed as immutable
+
+tests/ui/unique_while_shared.rs keeps that error checked, and
+tests/ui/pass/shared_then_unique.rs checks the version that compiles.
+
+*/
+
+/// Takes a shared (`&`) borrow of a place.
+#[macro_export]
+macro_rules! shared_borrow {
+    ($x:expr) => {
+        &$x
+    };
+}
+
+/// Takes a unique (`&mut`) borrow of a place.
+#[macro_export]
+macro_rules! unique_borrow {
+    ($x:expr) => {
+        &mut $x
+    };
+}
+
+/*
+
+Borrowing fields separately
+
+Only one unique borrow of a value can be live at a time, but the borrow checker
+tracks the fields of a struct one by one. Two &mut borrows of two different fields
+don't overlap, so both can be live at once.
+
+*/
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pair {
+    pub left: String,
+    pub right: String,
+}
+
+impl Pair {
+    /// Returns unique borrows of both fields at the same time.
+    ///
+    /// ```
+    /// use ownership::borrowing::Pair;
+    ///
+    /// let mut pair = Pair { left: String::from("a"), right: String::from("b") };
+    /// let (left, right) = pair.both_mut();
+    /// left.push('!');
+    /// right.push('?');
+    ///
+    /// assert_eq!(pair.left, "a!");
+    /// assert_eq!(pair.right, "b?");
+    /// ```
+    pub fn both_mut(&mut self) -> (&mut String, &mut String) {
+        (&mut self.left, &mut self.right)
+    }
+}
+
+/*
+
+Caching owned results
+
+A cache owns every result it has computed and lends them out on request. The
+caller's key is only borrowed for the lookup; an owned copy of it is made only
+the first time a key is seen, when the result has to be stored.
+
+*/
+
+/// Caches the results of an expensive `&str -> String` transform.
+#[derive(Debug, Default)]
+pub struct Memoizer {
+    cache: HashMap<String, String>,
+}
+
+impl Memoizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached result for `key`, running `compute` only if there
+    /// isn't one yet.
+    pub fn get_or_compute(&mut self, key: &str, compute: impl FnOnce(&str) -> String) -> &str {
+        if !self.cache.contains_key(key) {
+            let value = compute(key);
+            self.cache.insert(key.to_string(), value);
+        }
+
+        &self.cache[key]
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+/*
+
+Walking nested data
+
+A value can own other values that own values of their own. To look at all of it we
+don't need to take any of it apart: a shared reference to the outer value lets us
+borrow each inner one in turn, and the whole structure is still there when we're done.
+
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Str(String),
+    List(Vec<Value>),
+}
+
+/// Adds up the byte lengths of every String inside `value`, borrowing all the way down.
+pub fn deep_len(value: &Value) -> usize {
+    match value {
+        Value::Str(s) => s.len(),
+        Value::List(items) => items.iter().map(deep_len).sum(),
+    }
+}
+
+/*
+
+Borrowing for the length of a block
+
+with_borrows! takes borrows of a binding, names them, and keeps them alive for the
+whole of a block. Either any number of shared borrows:
+
+    with_borrows!(s => shared r1, r2; {
+        println!("{r1} {r2}");
+    });
+
+or exactly one unique borrow:
+
+    with_borrows!(s => unique r; {
+        r.push_str(", world");
+    });
+
+Asking for more than one unique borrow isn't even accepted by the macro:
+
+    // with_borrows!(s => unique a, b; { });
+
+    error: no rules expected `,`
+
+Inside the block the aliasing rules apply as usual, and the compiler points at the
+borrow that breaks them. Changing s while shared borrows are in use:
+
+    // with_borrows!(s => shared r; { s.push('!'); println!("{r}"); });
+
+    error[E0502]: cannot borrow `s` as mutable because it is also borrowed as immutable
+
+Taking a second unique borrow next to the one the macro made:
+
+    // with_borrows!(s => unique a; { let b = &mut s; a.push('x'); b.push('y'); });
+
+    error[E0499]: cannot borrow `s` as mutable more than once at a time
+
+Reading s while it is uniquely borrowed:
+
+    // with_borrows!(s => unique r; { println!("{s}"); r.push('x'); });
+
+    error[E0502]: cannot borrow `s` as immutable because it is also borrowed as mutable
+
+Each of these lives on as a compile-fail case in tests/ui: with_borrows_unique_list.rs,
+with_borrows_mut_while_shared.rs, with_borrows_two_mut.rs and
+with_borrows_use_while_mut.rs. tests/ui/pass/with_borrows.rs checks the two forms
+that do compile.
+
+*/
+
+/// Borrows a binding for the length of a block, either shared or unique.
+#[macro_export]
+macro_rules! with_borrows {
+    ($x:ident => shared $($r:ident),+ ; $body:block) => {{
+        $(let $r = &$x;)+
+        $body
+    }};
+    ($x:ident => unique $r:ident ; $body:block) => {{
+        let $r = &mut $x;
+        $body
+    }};
+}
+
+/*
+
+Reborrowing
+
+A &mut String can't be copied, since that would make two unique borrows. So how can
+a function that was handed one pass it along to another function and still use it
+afterwards? It doesn't pass the borrow itself; it makes a new, shorter one from it.
+
+*/
+
+/// Appends to `buf` by way of [`inner`], then keeps using `buf` afterwards.
+///
+/// `&mut *buf` is a reborrow: a new unique borrow of the same String that only
+/// lasts for the call to `inner`. While it exists, `buf` itself can't be used; as
+/// soon as `inner` returns, the reborrow ends and `buf` is usable again. Passing
+/// `buf` directly does the same thing, because the compiler reborrows a &mut
+/// argument automatically.
+pub fn outer(buf: &mut String) {
+    inner(&mut *buf);
+    buf.push('!');
+}
+
+pub fn inner(buf: &mut String) {
+    buf.push_str(", world");
+}
+
+/*
+
+Keeping indices instead of borrows
+
+It's tempting to store a String together with &str slices pointing into it, but
+the compiler won't allow that: the slices would borrow from the struct they live
+in, and moving the struct would move the String they point at. Storing byte ranges
+avoids the problem. They're plain numbers, so nothing is borrowed until someone
+asks for a sentence, and then the slice borrows from &self like any other.
+
+*/
+
+/// Owned text along with the byte range of each of it's sentences.
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    sentences: Vec<Range<usize>>,
+}
+
+impl Document {
+    /// Takes ownership of `text` and records where it's sentences are.
+    pub fn new(text: String) -> Self {
+        let sentences = sentence_spans(&text);
+        Document { text, sentences }
+    }
+
+    /// Borrows sentence number `i`, counting from 0.
+    pub fn sentence(&self, i: usize) -> Option<&str> {
+        let range = self.sentences.get(i)?;
+        Some(&self.text[range.clone()])
+    }
+
+    pub fn sentence_count(&self) -> usize {
+        self.sentences.len()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/*
+
+An owned store with borrowed views
+
+Env owns every key and value it holds, and only lends them out. expand returns a
+Cow: when the template has nothing to substitute, the template itself is borrowed
+back, and a new String is only built when a ${KEY} actually has to be replaced.
+
+*/
+
+/// Environment-style variables, kept in the order they were first set.
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    vars: Vec<(String, String)>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets `key` to an owned copy of `value`, replacing any earlier value.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.vars.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => {
+                v.clear();
+                v.push_str(value); // reuses the old value's buffer
+            }
+            None => self.vars.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Replaces every `${KEY}` in `template` with the value of `KEY`.
+    ///
+    /// Keys that aren't set are replaced with nothing, the way a shell does it. A
+    /// `${` with no closing `}` is left as it is. If nothing ends up replaced,
+    /// `template` is returned borrowed.
+    pub fn expand<'a>(&'a self, template: &'a str) -> Cow<'a, str> {
+        // only allocated once the first ${KEY} is found
+        let mut out: Option<String> = None;
+        let mut rest = template;
+        while let Some(open) = rest.find("${") {
+            let Some(close) = rest[open + 2..].find('}') else {
+                break;
+            };
+            let buf = out.get_or_insert_with(|| String::with_capacity(template.len()));
+            buf.push_str(&rest[..open]);
+            buf.push_str(self.get(&rest[open + 2..open + 2 + close]).unwrap_or(""));
+            rest = &rest[open + 2 + close + 1..];
+        }
+
+        match out {
+            Some(mut buf) => {
+                buf.push_str(rest);
+                Cow::Owned(buf)
+            }
+            None => Cow::Borrowed(template),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_lends_out_a_present_key() {
+        let mut config = Config::new();
+        config.get_or_insert("mode", "fast");
+        assert_eq!(config.get("mode"), Some("fast"));
+    }
+
+    #[test]
+    fn config_has_nothing_for_an_absent_key() {
+        assert_eq!(Config::new().get("mode"), None);
+    }
+
+    #[test]
+    fn config_get_or_insert_keeps_an_existing_value() {
+        let mut config = Config::new();
+        assert_eq!(config.get_or_insert("mode", "fast"), "fast");
+        assert_eq!(config.get_or_insert("mode", "slow"), "fast");
+        assert_eq!(config.get("mode"), Some("fast"));
+    }
+
+    #[test]
+    fn pair_both_mut_changes_both_fields() {
+        let mut pair = Pair {
+            left: String::from("a"),
+            right: String::from("b"),
+        };
+        let (left, right) = pair.both_mut();
+        left.push('!');
+        right.push('?');
+        std::mem::swap(left, right);
+        assert_eq!(pair.left, "b?");
+        assert_eq!(pair.right, "a!");
+    }
+
+    #[test]
+    fn memoizer_computes_once_per_key() {
+        let mut memo = Memoizer::new();
+        let mut calls = Vec::new();
+
+        for key in ["a", "b", "a", "a", "b"] {
+            let value = memo.get_or_compute(key, |k| {
+                calls.push(k.to_string());
+                k.to_uppercase()
+            });
+            assert_eq!(value, key.to_uppercase());
+        }
+
+        assert_eq!(calls, ["a", "b"]);
+        assert_eq!(memo.len(), 2);
+    }
+
+    #[test]
+    fn deep_len_sums_nested_strings_without_changing_them() {
+        let value = Value::List(vec![
+            Value::Str(String::from("ab")),
+            Value::List(vec![Value::Str(String::from("héllo")), Value::List(vec![])]),
+            Value::Str(String::new()),
+        ]);
+        let before = value.clone();
+
+        assert_eq!(deep_len(&value), 2 + 6);
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn outer_can_use_the_buffer_after_reborrowing_it() {
+        let mut buf = String::from("hello");
+        outer(&mut buf);
+        assert_eq!(buf, "hello, world!");
+
+        // and the caller still owns it afterwards
+        buf.push('?');
+        assert_eq!(buf, "hello, world!?");
+    }
+
+    #[test]
+    fn document_lends_out_each_sentence() {
+        let doc = Document::new(String::from("One. Two!  Three?"));
+        assert_eq!(doc.sentence_count(), 3);
+        assert_eq!(doc.sentence(0), Some("One."));
+        assert_eq!(doc.sentence(1), Some("Two!"));
+        assert_eq!(doc.sentence(2), Some("Three?"));
+        assert_eq!(doc.sentence(3), None);
+    }
+
+    #[test]
+    fn document_sentences_borrow_from_its_text() {
+        let doc = Document::new(String::from("Héllo wörld. Bye"));
+        let second = doc.sentence(1).unwrap();
+        assert_eq!(second, "Bye");
+        assert!(doc
+            .text()
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&second.as_ptr()));
+
+        // moving the document keeps the ranges valid, since they are just numbers
+        let moved = doc;
+        assert_eq!(moved.sentence(0), Some("Héllo wörld."));
+        assert_eq!(Document::new(String::new()).sentence_count(), 0);
+    }
+
+    #[test]
+    fn env_expand_without_substitutions_borrows_the_template() {
+        let env = Env::new();
+        assert!(matches!(
+            env.expand("plain text"),
+            Cow::Borrowed("plain text")
+        ));
+        // an unclosed ${ isn't a substitution either
+        assert!(matches!(env.expand("a ${ b"), Cow::Borrowed("a ${ b")));
+    }
+
+    #[test]
+    fn env_expand_replaces_each_key() {
+        let mut env = Env::new();
+        env.set("USER", "ana");
+        env.set("HOME", "/home/ana");
+
+        let out = env.expand("${USER} lives in ${HOME}, ${USER}!");
+        assert!(matches!(out, Cow::Owned(_)));
+        assert_eq!(out, "ana lives in /home/ana, ana!");
+    }
+
+    #[test]
+    fn env_expand_drops_missing_keys() {
+        let mut env = Env::new();
+        env.set("A", "1");
+        assert_eq!(env.expand("[${A}${MISSING}] ${"), "[1] ${");
+    }
+
+    #[test]
+    fn env_set_replaces_an_earlier_value() {
+        let mut env = Env::new();
+        env.set("A", "1");
+        env.set("A", "2");
+        assert_eq!(env.get("A"), Some("2"));
+        assert_eq!(env.get("B"), None);
+    }
+}
@@ -0,0 +1,468 @@
+/*
+
+Length and capacity
+
+A String's length is how many bytes it is using; it's capacity is how many bytes it
+got from the allocator. When a push needs more room than the capacity, the String
+asks for a bigger block, copies it's bytes over, and frees the old one. Reserving
+enough room up front avoids paying for those copies.
+
+*/
+
+use std::cell::RefCell;
+
+use crate::error::TextError;
+
+/// Collects chars into a String, reserving room for them before the first push.
+///
+/// The iterator's size hint gives the least number of chars it will produce, and
+/// every char takes at least one byte, so that many bytes are reserved up front.
+pub fn collect_string<I: Iterator<Item = char>>(iter: I) -> String {
+    let (lower, _) = iter.size_hint();
+    let mut out = String::with_capacity(lower);
+    for c in iter {
+        out.push(c);
+    }
+    out
+}
+
+/// Gives unused capacity back to the allocator and returns how many bytes that freed.
+pub fn shrink_and_report(buf: &mut String) -> usize {
+    let before = buf.capacity();
+    buf.shrink_to_fit();
+    before - buf.capacity()
+}
+
+/// Adds up the byte lengths of `parts`, or returns `None` if the total doesn't fit
+/// in a usize.
+///
+/// No single &str can be longer than isize::MAX bytes, but a slice can refer to
+/// the same long string many times over, and on a 32-bit target a few large parts
+/// are enough. A plain sum would panic in debug builds and wrap around in
+/// release builds, and then reserve far too little.
+pub fn checked_total_len(parts: &[&str]) -> Option<usize> {
+    checked_sum(parts.iter().map(|part| part.len()))
+}
+
+// the sum itself, apart so that overflow can be tested without strings that long
+fn checked_sum(lens: impl IntoIterator<Item = usize>) -> Option<usize> {
+    lens.into_iter().try_fold(0usize, usize::checked_add)
+}
+
+/// Joins `parts` into one String, using the first part's buffer as the result.
+///
+/// The first String is moved out of the vector rather than copied, so all of
+/// it's bytes and it's heap block are kept. Room for the rest is reserved once,
+/// then they are appended, which is at most one reallocation where building a
+/// fresh String would need a new allocation on top of the parts it's made from.
+pub fn concat_owned(parts: Vec<String>) -> String {
+    let mut parts = parts.into_iter();
+    let Some(mut out) = parts.next() else {
+        return String::new();
+    };
+    let rest = parts.as_slice();
+    let extra: usize = rest.iter().map(String::len).sum();
+    out.reserve(extra);
+    for part in parts {
+        out.push_str(&part);
+    }
+    out
+}
+
+/// Predicts the capacity a String or `Vec<u8>` with `current` capacity grows to when
+/// it must hold `needed` bytes in total.
+///
+/// If `needed` already fits, nothing grows. Otherwise the standard library at
+/// least doubles the capacity, so that a long run of pushes only reallocates a
+/// handful of times, and jumps straight to `needed` when that's bigger still. The
+/// first allocation is never smaller than 8 bytes. This mirrors what std does
+/// today for pushes and reserve; it's not something std promises to keep doing.
+pub fn next_capacity(current: usize, needed: usize) -> usize {
+    if needed <= current {
+        return current;
+    }
+    current.saturating_mul(2).max(needed).max(8)
+}
+
+/// Returns how many bytes longer the result of `transform` is than `s`, negative
+/// when it comes out shorter.
+///
+/// Lengths are compared, not capacities: the new String may well have asked
+/// the allocator for more than it ended up using.
+pub fn size_delta(s: &str, transform: impl Fn(&str) -> String) -> isize {
+    // neither length can be more than isize::MAX, so both casts are lossless
+    transform(s).len() as isize - s.len() as isize
+}
+
+/*
+
+Reusing a buffer
+
+Clearing a String sets it's length to zero but keeps it's capacity, so the next
+round of pushes can use the same heap block without asking the allocator again.
+Keeping one scratch String per thread lets every call reuse it.
+
+*/
+
+thread_local! {
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Lets `f` write into a per-thread scratch String and returns a copy of what
+/// it wrote.
+///
+/// The scratch buffer keeps it's capacity between calls, so once it has grown
+/// big enough, building the text no longer reallocates; only the returned copy
+/// is a fresh allocation. If `f` itself calls `format_reusing`, the inner call
+/// gets a fresh String instead, since the scratch buffer is already in use.
+pub fn format_reusing<F: FnOnce(&mut String)>(f: F) -> String {
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut buf) => {
+            buf.clear();
+            f(&mut buf);
+            let out = buf.clone();
+            buf.clear();
+            out
+        }
+        Err(_) => {
+            let mut buf = String::new();
+            f(&mut buf);
+            buf
+        }
+    })
+}
+
+/*
+
+A String with a fixed limit
+
+A const generic lets the limit be part of the type: BoundedString<16> and
+BoundedString<32> are different types. The buffer is allocated once with room for N
+bytes, and pushes that would need more than that are refused instead of growing it.
+
+*/
+
+/// A String that never holds more than `N` bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundedString<const N: usize> {
+    buf: String,
+}
+
+impl<const N: usize> BoundedString<N> {
+    pub fn new() -> Self {
+        BoundedString {
+            buf: String::with_capacity(N),
+        }
+    }
+
+    /// Appends `s`, or returns [`TextError::Capacity`] and leaves the content as
+    /// it was if that would go over `N` bytes.
+    pub fn push_str(&mut self, s: &str) -> Result<(), TextError> {
+        if s.len() > N - self.buf.len() {
+            return Err(TextError::Capacity);
+        }
+        self.buf.push_str(s);
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Bytes that can still be pushed.
+    pub fn remaining(&self) -> usize {
+        N - self.buf.len()
+    }
+}
+
+impl<const N: usize> Default for BoundedString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*
+
+Growing a String by recursion
+
+A recursive builder that owns one String and passes a &mut to it down each call
+only ever allocates for that one String, however deep it goes. What it can't do on
+it's own is stop: the depth comes from the caller, so the length is checked against
+a budget before any of it is built.
+
+*/
+
+/// The most bytes [`build_nested`] will build.
+pub const NESTED_BUDGET: usize = 4096;
+
+/// Builds `depth` pairs of brackets nested inside each other: 3 gives "[[[]]]".
+///
+/// The result takes two bytes per level. A depth that would need more than
+/// [`NESTED_BUDGET`] bytes returns [`TextError::Capacity`] without allocating,
+/// which also keeps the recursion from going deeper than half the budget.
+pub fn build_nested(depth: usize) -> Result<String, TextError> {
+    let len = depth.checked_mul(2).ok_or(TextError::Capacity)?;
+    if len > NESTED_BUDGET {
+        return Err(TextError::Capacity);
+    }
+
+    let mut out = String::with_capacity(len);
+    nest(&mut out, depth);
+    Ok(out)
+}
+
+// writes one level and recurses for the ones inside it
+fn nest(out: &mut String, depth: usize) {
+    if depth == 0 {
+        return;
+    }
+    out.push('[');
+    nest(out, depth - 1);
+    out.push(']');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_string_keeps_every_char() {
+        assert_eq!(collect_string("héllo".chars()), "héllo");
+        assert_eq!(collect_string("".chars()), "");
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn collect_string_allocates_less_than_pushing_char_by_char() {
+        use crate::alloc_count::count_allocations;
+
+        let chars = || std::iter::repeat_n('x', 100);
+        let (_, reserved) = count_allocations(|| collect_string(chars()));
+        let (_, naive) = count_allocations(|| {
+            let mut out = String::new();
+            for c in chars() {
+                out.push(c);
+            }
+            out
+        });
+        assert_eq!(reserved, 1);
+        assert!(naive > reserved, "{naive} should be more than {reserved}");
+    }
+
+    #[test]
+    fn format_reusing_returns_what_was_written() {
+        assert_eq!(format_reusing(|s| s.push_str("first")), "first");
+        // the scratch buffer was cleared in between
+        assert_eq!(format_reusing(|s| s.push_str("second")), "second");
+        assert_eq!(
+            format_reusing(|s| {
+                s.push_str("outer ");
+                s.push_str(&format_reusing(|inner| inner.push_str("inner")));
+            }),
+            "outer inner"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn format_reusing_only_allocates_the_copy_once_warm() {
+        use crate::alloc_count::count_allocations;
+
+        let write = |s: &mut String| {
+            for _ in 0..10 {
+                s.push_str("some text ");
+            }
+        };
+        format_reusing(write); // grows the scratch buffer to size
+
+        for _ in 0..5 {
+            let (out, n) = count_allocations(|| format_reusing(write));
+            assert_eq!(out.len(), 100);
+            assert_eq!(n, 1, "only the returned copy should allocate");
+        }
+    }
+
+    #[test]
+    fn bounded_string_fills_to_n_and_then_refuses() {
+        let mut s = BoundedString::<8>::new();
+        s.push_str("abcd").unwrap();
+        s.push_str("éfg").unwrap();
+        assert_eq!(s.len(), 8);
+        assert_eq!(s.remaining(), 0);
+
+        let capacity = s.buf.capacity();
+        assert_eq!(s.push_str("h"), Err(TextError::Capacity));
+        assert_eq!(
+            s.buf.capacity(),
+            capacity,
+            "a refused push must not grow the buffer"
+        );
+        assert_eq!(s.as_str(), "abcdéfg");
+    }
+
+    #[test]
+    fn bounded_string_refuses_a_push_that_would_overflow() {
+        let mut s = BoundedString::<4>::default();
+        s.push_str("ab").unwrap();
+        assert_eq!(s.push_str("cde"), Err(TextError::Capacity));
+        assert_eq!(s.as_str(), "ab");
+    }
+
+    #[test]
+    fn shrink_and_report_frees_the_excess() {
+        let mut buf = String::from("hello");
+        buf.reserve_exact(100);
+        let excess = buf.capacity() - buf.len();
+
+        assert_eq!(shrink_and_report(&mut buf), excess);
+        assert_eq!(buf.capacity(), buf.len());
+        assert_eq!(shrink_and_report(&mut buf), 0);
+    }
+
+    #[test]
+    fn checked_total_len_adds_up_the_parts() {
+        assert_eq!(checked_total_len(&["ab", "é", ""]), Some(4));
+        assert_eq!(checked_total_len(&[]), Some(0));
+    }
+
+    #[test]
+    fn checked_sum_reports_overflow_as_none() {
+        assert_eq!(checked_sum([usize::MAX, 0]), Some(usize::MAX));
+        assert_eq!(checked_sum([usize::MAX, 1]), None);
+        assert_eq!(checked_sum([usize::MAX / 2 + 1; 2]), None);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn checked_total_len_of_one_long_str_many_times_overflows() {
+        // 4096 borrows of the same 1 MiB string add up to 2^32 bytes
+        let long = "x".repeat(1 << 20);
+        let parts = vec![long.as_str(); 4096];
+        assert_eq!(checked_total_len(&parts), None);
+        assert_eq!(checked_total_len(&parts[..4095]), Some(4095 << 20));
+    }
+
+    #[test]
+    fn concat_owned_matches_plain_concatenation() {
+        let parts = vec![
+            String::from("hé"),
+            String::new(),
+            String::from("llo"),
+            String::from("!"),
+        ];
+        assert_eq!(concat_owned(parts.clone()), parts.concat());
+        assert_eq!(concat_owned(Vec::new()), "");
+    }
+
+    #[test]
+    fn concat_owned_keeps_the_first_buffer() {
+        let first = String::with_capacity(16) + "ab";
+        let heap = first.as_ptr();
+        let joined = concat_owned(vec![first, String::from("cd")]);
+        assert_eq!(joined, "abcd");
+        assert_eq!(joined.as_ptr(), heap);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn concat_owned_allocates_less_than_a_fresh_string() {
+        use crate::alloc_count::count_allocations;
+
+        let parts = || vec![String::with_capacity(32) + "first", String::from("second")];
+
+        let owned = parts();
+        let (_, reused) = count_allocations(|| concat_owned(owned));
+        let fresh_parts = parts();
+        let (_, fresh) = count_allocations(|| fresh_parts.concat());
+        assert_eq!(reused, 0, "the first part already had room for the rest");
+        assert_eq!(fresh, 1);
+
+        // without spare room, growing the first buffer is still a single realloc
+        let tight = vec![
+            String::from("first"),
+            String::from("second"),
+            String::from("third"),
+        ];
+        let (_, n) = count_allocations(|| concat_owned(tight));
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn next_capacity_starts_at_eight() {
+        assert_eq!(next_capacity(0, 5), 8);
+        assert_eq!(next_capacity(0, 1), 8);
+    }
+
+    #[test]
+    fn next_capacity_doubles() {
+        assert_eq!(next_capacity(8, 9), 16);
+        assert_eq!(next_capacity(16, 17), 32);
+    }
+
+    #[test]
+    fn next_capacity_jumps_to_a_bigger_need() {
+        assert_eq!(next_capacity(8, 100), 100);
+        assert_eq!(next_capacity(0, 9), 9);
+    }
+
+    #[test]
+    fn next_capacity_with_room_left_stays_put() {
+        assert_eq!(next_capacity(16, 16), 16);
+        assert_eq!(next_capacity(16, 3), 16);
+        assert_eq!(next_capacity(0, 0), 0);
+    }
+
+    #[test]
+    fn size_delta_of_a_growing_transform() {
+        assert_eq!(size_delta("abc", |s| s.repeat(2)), 3);
+        assert_eq!(size_delta("é", |s| format!("[{s}]")), 2);
+        // 'ȿ' is two bytes, and it's uppercase 'Ȿ' three
+        assert_eq!(size_delta("ȿ", str::to_uppercase), 1);
+    }
+
+    #[test]
+    fn size_delta_of_a_shrinking_transform() {
+        assert_eq!(size_delta("  padded  ", |s| s.trim().to_string()), -4);
+        assert_eq!(size_delta("héllo", |_| String::new()), -6);
+    }
+
+    #[test]
+    fn size_delta_of_a_same_length_result_is_zero() {
+        assert_eq!(size_delta("abc", str::to_uppercase), 0);
+        // 'ß' is two bytes and uppercases to "SS", two bytes
+        assert_eq!(size_delta("straße", str::to_uppercase), 0);
+        assert_eq!(size_delta("same", str::to_string), 0);
+        assert_eq!(size_delta("", str::to_string), 0);
+    }
+
+    #[test]
+    fn build_nested_of_a_small_depth() {
+        assert_eq!(build_nested(0).unwrap(), "");
+        assert_eq!(build_nested(1).unwrap(), "[]");
+        assert_eq!(build_nested(3).unwrap(), "[[[]]]");
+    }
+
+    #[test]
+    fn build_nested_up_to_the_budget() {
+        let full = build_nested(NESTED_BUDGET / 2).unwrap();
+        assert_eq!(full.len(), NESTED_BUDGET);
+        assert_eq!(
+            build_nested(NESTED_BUDGET / 2 + 1),
+            Err(TextError::Capacity)
+        );
+    }
+
+    #[test]
+    fn build_nested_of_an_excessive_depth_is_refused() {
+        assert_eq!(build_nested(usize::MAX), Err(TextError::Capacity));
+        assert_eq!(build_nested(usize::MAX / 2 + 1), Err(TextError::Capacity));
+    }
+}
@@ -0,0 +1,56 @@
+/*
+
+Checking an invariant once
+
+Ownership uses types to rule out certain mistakes before the program runs. We can
+do the same for our own rules: if the only way to make a value is through a
+function that checks the rule, then every value of that type is known to follow
+it, and code that uses the value doesn't need to check again.
+
+*/
+
+/// A &str that is known not to be empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NonEmptyStr<'a>(&'a str);
+
+impl<'a> NonEmptyStr<'a> {
+    /// Returns `None` for an empty string. This is the only way to build one.
+    pub fn new(s: &'a str) -> Option<Self> {
+        if s.is_empty() {
+            None
+        } else {
+            Some(NonEmptyStr(s))
+        }
+    }
+
+    pub fn first_char(&self) -> char {
+        self.0
+            .chars()
+            .next()
+            .expect("NonEmptyStr::new turns away empty strings")
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_turns_away_only_the_empty_string() {
+        assert_eq!(NonEmptyStr::new(""), None);
+        assert!(NonEmptyStr::new(" ").is_some());
+    }
+
+    #[test]
+    fn first_char_and_as_str() {
+        let text = String::from("élan");
+        let s = NonEmptyStr::new(&text).unwrap();
+        assert_eq!(s.first_char(), 'é');
+        assert_eq!(s.as_str(), "élan");
+        assert_eq!(s.as_str().as_ptr(), text.as_ptr());
+    }
+}
@@ -0,0 +1,113 @@
+/*
+
+Closures and ownership
+
+A closure can capture the variables around it in three ways: by shared reference,
+by mutable reference, or by taking ownership of them. Rust picks the least it needs
+from how the body uses each variable, and the move keyword forces every capture to
+take ownership instead.
+
+A closure that is returned from a function has to own everything it captured,
+since the function's locals are dropped when it returns. Capturing one of them by
+reference would leave the closure holding a dangling reference, so the compiler
+rejects it:
+
+fn make_greeter() -> impl Fn() -> String {
+    let greeting = String::from("hi");
+    || greeting.clone()
+}
+
+error[E0373]: closure may outlive the current function, but it borrows `greeting`,
+which is owned by the current function
+
+Adding move in front of the closure moves greeting into it, and that fixes it.
+
+*/
+
+/// Returns a closure that puts `prefix` in front of whatever it's given.
+///
+/// `prefix` is moved into the closure, which owns it from then on. Each call
+/// only reads it, cloning it to start a fresh String, so the closure is Fn and
+/// can be called any number of times.
+pub fn make_appender(prefix: String) -> impl Fn(&str) -> String {
+    move |s| {
+        let mut out = prefix.clone();
+        out.push_str(s);
+        out
+    }
+}
+
+/// Returns a closure that counts how many times it has been called, starting at 1.
+///
+/// The count is moved into the closure, so it lives as long as the closure
+/// does. Inside the body, `count += 1` works through a mutable reference to that
+/// captured count, which is why the closure is FnMut and must itself be mutable
+/// to be called.
+pub fn make_counter() -> impl FnMut() -> usize {
+    let mut count = 0;
+    move || {
+        count += 1;
+        count
+    }
+}
+
+/*
+
+Moving into a thread
+
+A spawned thread can keep running after the function that spawned it returns, so
+std::thread::spawn only takes closures that own everything they capture. The move
+closure takes the String with it to the new thread, and the spawning thread can't
+touch it again:
+
+    let data = String::from("hello");
+    let handle = std::thread::spawn(move || data.len());
+    println!("{data}");
+
+    error[E0382]: borrow of moved value: `data`
+
+spawn_with_owned below takes data by value for the same reason, and using data
+after handing it over fails the same way; tests/ui/spawn_with_owned_then_use.rs
+keeps that error checked.
+
+The thread hands it's result back through the JoinHandle when it is joined.
+
+*/
+
+/// Moves `data` to a new thread, which returns it's length in bytes.
+///
+/// `data` is dropped on that thread once the length has been worked out; join
+/// the handle to get the length back.
+pub fn spawn_with_owned(data: String) -> std::thread::JoinHandle<usize> {
+    std::thread::spawn(move || data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_appender_can_be_called_again_and_again() {
+        let greet = make_appender(String::from("hi, "));
+        assert_eq!(greet("ana"), "hi, ana");
+        assert_eq!(greet("bo"), "hi, bo");
+        assert_eq!(make_appender(String::new())("é"), "é");
+    }
+
+    #[test]
+    fn make_counter_keeps_its_own_count() {
+        let mut a = make_counter();
+        let mut b = make_counter();
+        assert_eq!(a(), 1);
+        assert_eq!(a(), 2);
+        assert_eq!(b(), 1);
+        assert_eq!(a(), 3);
+    }
+
+    #[test]
+    fn spawn_with_owned_returns_the_length_on_join() {
+        let handle = spawn_with_owned(String::from("héllo"));
+        assert_eq!(handle.join().unwrap(), 6);
+        assert_eq!(spawn_with_owned(String::new()).join().unwrap(), 0);
+    }
+}
@@ -0,0 +1,61 @@
+/*
+
+Dangling references
+
+A function can't hand back a reference to a value it created itself. The value is
+dropped when the function returns, so the reference would point at freed memory.
+The naive version doesn't compile:
+
+    fn make_reference() -> &'static String {
+        let s = String::from("hello");
+        &s
+    } // s is dropped here, while the caller would still hold &s
+
+    error[E0515]: cannot return reference to local variable `s`
+
+Without the 'static the compiler stops even earlier, at the signature, because
+there is nothing for the returned reference to borrow from:
+
+    error[E0106]: missing lifetime specifier
+
+Both are kept compiling-or-not by tests/ui/make_reference.rs and
+tests/ui/make_reference_no_lifetime.rs.
+
+There are two ways out. Either give the caller the value itself, moving ownership
+out of the function, or borrow from something the caller already owns, so the
+value outlives the call.
+
+*/
+
+/// Creates a String and moves it out to the caller, who becomes it's owner.
+pub fn make_owned() -> String {
+    String::from("hello")
+}
+
+/// Returns `owner` with surrounding whitespace trimmed, as a slice into `owner`.
+///
+/// The returned &str borrows from `owner`, not from anything local to this
+/// function, so it stays valid after we return.
+pub fn borrow_trimmed(owner: &str) -> &str {
+    owner.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_owned_hands_over_the_string() {
+        let mut s = make_owned();
+        s.push_str(", world"); // we own it, so we can change it
+        assert_eq!(s, "hello, world");
+    }
+
+    #[test]
+    fn borrow_trimmed_points_into_the_owner() {
+        let owner = String::from("  hello  ");
+        let trimmed = borrow_trimmed(&owner);
+        assert_eq!(trimmed, "hello");
+        assert_eq!(trimmed.as_ptr(), owner[2..].as_ptr());
+    }
+}
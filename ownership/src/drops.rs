@@ -0,0 +1,122 @@
+/*
+
+Controlling when values are dropped
+
+Normally a value is dropped when it's owner goes out of scope, and the elements of a
+Vec are dropped front to back when the Vec is. Because the owner decides when a value
+goes away, it can also choose to give values up early, one at a time, in whatever
+order it likes.
+
+*/
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::TextError;
+
+/// The names of dropped [`Loud`] markers, in the order they were dropped.
+pub type DropLog = Rc<RefCell<Vec<&'static str>>>;
+
+/// A marker that writes it's name to a shared log when it is dropped, so drop
+/// order can be seen.
+#[derive(Debug)]
+pub struct Loud {
+    pub name: &'static str,
+    log: DropLog,
+}
+
+impl Loud {
+    /// Makes a marker that will record `name` in `log` when dropped. The marker
+    /// holds a clone of the Rc, so the log outlives every marker written to it.
+    pub fn new(name: &'static str, log: &DropLog) -> Self {
+        Loud {
+            name,
+            log: Rc::clone(log),
+        }
+    }
+}
+
+impl Drop for Loud {
+    fn drop(&mut self) {
+        self.log.borrow_mut().push(self.name);
+    }
+}
+
+/// Drops the elements of `values` in the order given by `order`.
+///
+/// `order` must name every index of `values` exactly once. An index past the end
+/// gives [`TextError::OutOfBounds`], and a missing or repeated one gives
+/// [`TextError::InvalidOrder`]. Either way nothing is dropped early, and the
+/// values go away in their usual front-to-back order when the function returns.
+pub fn drop_in_order<T>(values: Vec<T>, order: &[usize]) -> Result<(), TextError> {
+    let mut seen = vec![false; values.len()];
+    for &i in order {
+        match seen.get_mut(i) {
+            None => return Err(TextError::OutOfBounds),
+            Some(true) => return Err(TextError::InvalidOrder),
+            Some(slot) => *slot = true,
+        }
+    }
+    if order.len() != values.len() {
+        return Err(TextError::InvalidOrder);
+    }
+
+    // wrapping each element in an Option lets us move it out and leave None behind
+    let mut slots: Vec<Option<T>> = values.into_iter().map(Some).collect();
+    for &i in order {
+        drop(slots[i].take());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn louds(names: &[&'static str], log: &DropLog) -> Vec<Loud> {
+        names.iter().map(|&name| Loud::new(name, log)).collect()
+    }
+
+    #[test]
+    fn loud_logs_the_name_when_dropped() {
+        let log = DropLog::default();
+        let a = Loud::new("a", &log);
+        assert!(log.borrow().is_empty());
+        drop(a);
+        assert_eq!(*log.borrow(), ["a"]);
+    }
+
+    #[test]
+    fn a_vec_drops_front_to_back() {
+        let log = DropLog::default();
+        drop(louds(&["a", "b", "c"], &log));
+        assert_eq!(*log.borrow(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn drop_in_order_follows_the_given_order() {
+        let log = DropLog::default();
+        drop_in_order(louds(&["a", "b", "c"], &log), &[2, 0, 1]).unwrap();
+        assert_eq!(*log.borrow(), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn drop_in_order_rejects_orders_that_are_not_permutations() {
+        let cases = [
+            (&[0, 1][..], TextError::InvalidOrder),
+            (&[0, 0, 1], TextError::InvalidOrder),
+            (&[0, 1, 2, 0], TextError::InvalidOrder),
+            (&[0, 1, 3], TextError::OutOfBounds),
+        ];
+        for (order, expected) in cases {
+            let log = DropLog::default();
+            assert_eq!(
+                drop_in_order(louds(&["a", "b", "c"], &log), order),
+                Err(expected)
+            );
+            // nothing was dropped early, so the Vec went away front to back
+            assert_eq!(*log.borrow(), ["a", "b", "c"]);
+        }
+    }
+}
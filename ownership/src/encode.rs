@@ -0,0 +1,106 @@
+/*
+
+Encoding into a caller's buffer
+
+An encoder that returns a new String allocates every time it is called. Taking a
+&mut String instead lets the caller decide where the output goes; it can reuse
+one buffer across many calls and only pay for growing it when it runs out of room.
+
+*/
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Appends the percent-encoded form of `s` to `out`.
+///
+/// ASCII letters, digits and `-` `.` `_` `~` are copied as they are. Every other
+/// byte, including each byte of a multibyte char, becomes `%XX`.
+pub fn percent_encode(s: &str, out: &mut String) {
+    // every byte needs at least one byte of output
+    out.reserve(s.len());
+
+    for &b in s.as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push('%');
+            out.push(HEX[(b >> 4) as usize] as char);
+            out.push(HEX[(b & 0xF) as usize] as char);
+        }
+    }
+}
+
+/// Appends `s` to `out` escaped for use inside a JSON string. The surrounding
+/// quotes are not added.
+///
+/// Quotes and backslashes get a backslash, common control chars get their short
+/// forms (`\n`, `\t`, ...), and any other control char becomes `\u00XX`.
+/// Everything else, multibyte chars included, is copied as is.
+pub fn json_escape(s: &str, out: &mut String) {
+    out.reserve(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let b = c as u8;
+                out.push_str("\\u00");
+                out.push(HEX[(b >> 4) as usize] as char);
+                out.push(HEX[(b & 0xF) as usize] as char);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encode_a_space() {
+        let mut out = String::new();
+        percent_encode("a b", &mut out);
+        assert_eq!(out, "a%20b");
+    }
+
+    #[test]
+    fn percent_encode_each_byte_of_a_multibyte_char() {
+        let mut out = String::from("q=");
+        percent_encode("é-~", &mut out);
+        assert_eq!(out, "q=%C3%A9-~");
+    }
+
+    #[test]
+    fn percent_encode_of_nothing_leaves_the_buffer_alone() {
+        let mut out = String::from("keep");
+        percent_encode("", &mut out);
+        assert_eq!(out, "keep");
+    }
+
+    #[test]
+    fn json_escape_quotes_and_newlines() {
+        let mut out = String::new();
+        json_escape("say \"hi\"\\\n\tbye\r", &mut out);
+        assert_eq!(out, r#"say \"hi\"\\\n\tbye\r"#);
+    }
+
+    #[test]
+    fn json_escape_leaves_plain_text_alone() {
+        let mut out = String::from("[");
+        json_escape("plain ASCII, and é", &mut out);
+        assert_eq!(out, "[plain ASCII, and é");
+    }
+
+    #[test]
+    fn json_escape_other_control_chars_as_unicode_escapes() {
+        let mut out = String::new();
+        json_escape("\u{0}\u{8}\u{c}\u{1f}", &mut out);
+        assert_eq!(out, r"\u0000\b\f\u001F");
+    }
+}
@@ -0,0 +1,118 @@
+/*
+
+Errors
+
+Every fallible helper in this crate reports failure with the same TextError enum.
+Callers get one type to match on, and the ? operator can turn the standard library's
+own errors into a TextError through the From impls below.
+
+*/
+
+use std::collections::TryReserveError;
+use std::fmt;
+use std::str::Utf8Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextError {
+    /// The bytes were not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// An index or range fell outside the text, or off a char boundary.
+    OutOfBounds,
+    /// The result would not fit in the space allowed for it.
+    Capacity,
+    /// The text had to be ASCII but wasn't.
+    NotAscii,
+    /// A list of indices that had to name each index exactly once didn't.
+    InvalidOrder,
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::InvalidUtf8(e) => write!(f, "invalid UTF-8: {e}"),
+            TextError::OutOfBounds => write!(f, "index out of bounds"),
+            TextError::Capacity => write!(f, "not enough capacity"),
+            TextError::NotAscii => write!(f, "text is not ASCII"),
+            TextError::InvalidOrder => write!(f, "order must name each index exactly once"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for TextError {
+    fn from(e: Utf8Error) -> Self {
+        TextError::InvalidUtf8(e)
+    }
+}
+
+impl From<TryReserveError> for TextError {
+    fn from(_: TryReserveError) -> Self {
+        TextError::Capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_utf8() -> Utf8Error {
+        // black_box keeps the compiler from flagging a literal it knows is invalid
+        let bytes = std::hint::black_box([b'a', 0xFF]);
+        std::str::from_utf8(&bytes).unwrap_err()
+    }
+
+    #[test]
+    fn display_strings() {
+        assert_eq!(
+            TextError::InvalidUtf8(invalid_utf8()).to_string(),
+            "invalid UTF-8: invalid utf-8 sequence of 1 bytes from index 1"
+        );
+        assert_eq!(TextError::OutOfBounds.to_string(), "index out of bounds");
+        assert_eq!(TextError::Capacity.to_string(), "not enough capacity");
+        assert_eq!(TextError::NotAscii.to_string(), "text is not ASCII");
+        assert_eq!(
+            TextError::InvalidOrder.to_string(),
+            "order must name each index exactly once"
+        );
+    }
+
+    #[test]
+    fn question_mark_converts_a_utf8_error() {
+        fn parse(bytes: &[u8]) -> Result<&str, TextError> {
+            Ok(std::str::from_utf8(bytes)?)
+        }
+
+        assert_eq!(parse(b"ok"), Ok("ok"));
+        assert_eq!(
+            parse(&[b'a', 0xFF]),
+            Err(TextError::InvalidUtf8(invalid_utf8()))
+        );
+    }
+
+    #[test]
+    fn question_mark_converts_a_try_reserve_error() {
+        fn reserve(n: usize) -> Result<String, TextError> {
+            let mut s = String::new();
+            s.try_reserve(n)?;
+            Ok(s)
+        }
+
+        assert_eq!(reserve(usize::MAX), Err(TextError::Capacity));
+    }
+
+    #[test]
+    fn only_invalid_utf8_has_a_source() {
+        use std::error::Error;
+
+        assert!(TextError::InvalidUtf8(invalid_utf8()).source().is_some());
+        assert!(TextError::OutOfBounds.source().is_none());
+    }
+}
@@ -0,0 +1,105 @@
+/*
+
+Handing buffers to C
+
+C code works with raw pointers, and a raw pointer says nothing about who owns the
+memory behind it or how long it stays valid. If we free a buffer while C still has
+a pointer into it, or free it twice, we're back to the use-after-free and double
+free bugs that ownership exists to rule out.
+
+The fix is to keep the buffer owned by a Rust value for as long as the pointer is
+in use. The pointer is only valid while that owner is alive, and the owner frees
+the buffer exactly once, when it is dropped.
+
+*/
+
+/// An owned, NUL-terminated copy of some text that can be lent to C.
+#[derive(Debug)]
+pub struct OwnedCBuffer {
+    bytes: Box<[u8]>,
+}
+
+impl OwnedCBuffer {
+    pub fn new(s: &str) -> Self {
+        let mut bytes = Vec::with_capacity(s.len() + 1);
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.push(0);
+        OwnedCBuffer {
+            bytes: bytes.into_boxed_slice(),
+        }
+    }
+
+    /// A pointer to the first byte. It must not be used after `self` is dropped.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.bytes.as_ptr()
+    }
+
+    /// Length in bytes, not counting the trailing NUL.
+    pub fn len(&self) -> usize {
+        self.bytes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len()]
+    }
+}
+
+// There is no Drop impl: the Box inside frees the buffer when an OwnedCBuffer is
+// dropped, and a value is only ever dropped once, so the buffer is freed exactly
+// once without any code of our own.
+
+/// Copies `s` into an [`OwnedCBuffer`] and lends it's pointer and length to `f`.
+///
+/// The buffer lives until `f` returns and is freed right after, so the pointer
+/// can't outlive it as long as `f` doesn't stash it somewhere.
+pub fn with_c_buffer<R>(s: &str, f: impl FnOnce(*const u8, usize) -> R) -> R {
+    let buffer = OwnedCBuffer::new(s);
+    f(buffer.as_ptr(), buffer.len())
+} // buffer is dropped here, after f is done with the pointer
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_c_buffer_holds_the_text_and_a_nul() {
+        let buffer = OwnedCBuffer::new("héllo");
+        assert_eq!(buffer.len(), 6);
+        assert_eq!(buffer.as_bytes(), "héllo".as_bytes());
+        // SAFETY: the buffer is alive and has len() bytes plus the NUL
+        let with_nul = unsafe { std::slice::from_raw_parts(buffer.as_ptr(), buffer.len() + 1) };
+        assert_eq!(with_nul.last(), Some(&0));
+        assert!(OwnedCBuffer::new("").is_empty());
+    }
+
+    #[test]
+    fn with_c_buffer_lends_the_text_to_the_closure() {
+        let copied = with_c_buffer("abc", |ptr, len| {
+            // SAFETY: the pointer is valid for len bytes until the closure returns
+            unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec()
+        });
+        assert_eq!(copied, b"abc");
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn with_c_buffer_frees_the_buffer_once_after_the_closure() {
+        use crate::alloc_count::frees_so_far;
+
+        let before = frees_so_far();
+        let len = with_c_buffer("abc", |_, len| {
+            assert_eq!(
+                frees_so_far(),
+                before,
+                "freed while the pointer was lent out"
+            );
+            len
+        });
+        assert_eq!(len, 3);
+        assert_eq!(frees_so_far(), before + 1);
+    }
+}
@@ -0,0 +1,34 @@
+/*
+
+The ownership library
+
+main.rs walks through the ownership rules as prose and small examples. The modules
+here put those rules to work: each helper is written to show who owns a value, who
+only borrows it, and when heap memory is allocated or handed back.
+
+*/
+
+pub mod analysis;
+pub mod borrowing;
+pub mod capacity;
+pub mod checked;
+pub mod closures;
+pub mod dangling;
+pub mod drops;
+pub mod encode;
+pub mod error;
+pub mod ffi;
+pub mod moves;
+pub mod parse;
+pub mod rope;
+pub mod shared;
+pub mod slab;
+pub mod storage;
+pub mod streams;
+pub mod text;
+pub mod trace;
+pub mod utf8;
+pub mod words;
+
+#[cfg(feature = "count-allocations")]
+pub mod alloc_count;
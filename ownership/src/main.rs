@@ -1,3 +1,5 @@
+#![allow(clippy::unused_unit)] // the tutorial spells out `-> ()` on purpose
+
 /*
 
 Ownership enables rust to make memory safety guarantees without needing a garbage collector.
@@ -186,7 +188,7 @@ Check out what happens when you try to use s1 after s2 is created; it won’t wo
 
 fn _test() -> () {
     let s1 = String::from("hello");
-    let s2 = s1;
+    let _s2 = s1;
 
     // println!("{s1}, world!");
     // this is an error of invalidated reference
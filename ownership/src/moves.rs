@@ -0,0 +1,713 @@
+/*
+
+Moves
+
+A move copies the pointer, length and capacity of a value and leaves the heap data
+where it is. Nothing runs when that happens, so a program can't observe a move
+directly. The helpers here make moves visible, or show how to move a value out of a
+place while leaving that place in a usable state.
+
+*/
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::error::TextError;
+
+/// Wraps a value and counts how many times it was passed on with [`relocate`].
+///
+/// Real moves can't be counted, so `relocate` stands in for one: call it at each
+/// point the value crosses a function boundary.
+///
+/// There is no Cell and no Drop impl. `relocate` takes the wrapper by value, so the
+/// count is a plain field changed through an owner, with no shared mutation for a
+/// Cell to allow. A Drop impl would stop `into_inner` from moving `value` out
+/// (error E0509), forcing an Option or unsafe code just to hand the value back, and
+/// it couldn't see real moves anyway, since moving a value runs no code.
+///
+/// [`relocate`]: MoveCounted::relocate
+#[derive(Debug)]
+pub struct MoveCounted<T> {
+    value: T,
+    moves: usize,
+}
+
+impl<T> MoveCounted<T> {
+    pub fn new(value: T) -> Self {
+        MoveCounted { value, moves: 0 }
+    }
+
+    /// Takes ownership of the wrapper and hands it back with one more move counted.
+    pub fn relocate(mut self) -> Self {
+        self.moves += 1;
+        self
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Gives up the wrapper and returns ownership of the value inside.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/*
+
+Moving out of a place
+
+We can't move a value out from behind a &mut and leave nothing there, because the
+owner still expects a valid value when it gets the reference back. What we can do
+is move the value out and put another one in it's place in the same step, which
+is what std::mem::swap and std::mem::replace do.
+
+*/
+
+/// Swaps the contents of two Options. Only the Options themselves move; whatever
+/// they hold is never cloned.
+pub fn swap_options<T>(a: &mut Option<T>, b: &mut Option<T>) {
+    std::mem::swap(a, b);
+}
+
+/// Moves the value out of `slot`, leaving `None` behind.
+///
+/// This is `std::mem::replace(slot, None)`, which Option provides as `take`.
+pub fn take_option<T>(slot: &mut Option<T>) -> Option<T> {
+    slot.take()
+}
+
+/*
+
+Partial moves
+
+Each field of a struct is it's own value, so we can move one field out and leave the
+others where they are. After that the struct is partially moved: the fields that
+are left can still be used one by one, but the struct as a whole can't, since part
+of it is gone.
+
+    let record = Record { name: String::from("a"), tags: Vec::new() };
+    let name = record.name;  // moves just the name out
+    let tags = &record.tags; // fine, tags was not moved
+    // let whole = record;   // not fine, name is missing
+
+    error[E0382]: use of partially moved value: `record`
+
+tests/ui/partial_move.rs keeps that error checked.
+
+Moving every field out at once, like into_parts does, is how we take a struct apart
+on purpose.
+
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+impl Record {
+    /// Consumes the record and moves both of it's fields out to the caller.
+    pub fn into_parts(self) -> (String, Vec<String>) {
+        (self.name, self.tags)
+    }
+}
+
+/*
+
+Moving through a queue
+
+A queue that takes values by value owns them while they wait. Pushing moves a value
+in, popping moves it back out to whoever asked, and at no point does the queue
+need to clone anything.
+
+*/
+
+/// A first-in, first-out queue that takes ownership of it's items.
+#[derive(Debug, Default)]
+pub struct MoveQueue<T> {
+    items: VecDeque<T>,
+}
+
+impl<T> MoveQueue<T> {
+    pub fn new() -> Self {
+        MoveQueue {
+            items: VecDeque::new(),
+        }
+    }
+
+    /// Moves `item` into the back of the queue.
+    pub fn push(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    /// Moves the front item out to the caller.
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Moves the elements of `v` into owned chunks of `chunk_size`, in order. The last
+/// chunk is shorter if the elements don't divide evenly.
+///
+/// Each element is moved, never cloned, so a String's heap data stays where it is.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0, like `slice::chunks`.
+pub fn into_chunks<T>(v: Vec<T>, chunk_size: usize) -> Vec<Vec<T>> {
+    assert!(chunk_size != 0, "chunk_size must not be zero");
+
+    let mut chunks = Vec::with_capacity(v.len().div_ceil(chunk_size));
+    let mut current = Vec::new();
+    for item in v {
+        // a chunk's room is only asked for once an item is there to fill it, so an
+        // even split doesn't leave an unused Vec behind once the last one fills
+        if current.is_empty() {
+            current.reserve_exact(chunk_size);
+        }
+        current.push(item);
+        if current.len() == chunk_size {
+            chunks.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Merges two sorted lists into one sorted list, consuming both.
+///
+/// Each String is moved into the output, so only the Vec that holds them is new.
+/// When both lists have equal words, the one from `a` comes first.
+pub fn merge_sorted(a: Vec<String>, b: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if x <= y {
+            out.extend(a.next());
+        } else {
+            out.extend(b.next());
+        }
+    }
+    out.extend(a);
+    out.extend(b);
+    out
+}
+
+/// Moves element `idx` out of `arr`, leaving `T::default()` in it's place.
+///
+/// An array can't have a hole in it, so moving one element out means putting
+/// something else there. For a String the default is an empty String, which
+/// doesn't allocate. Returns [`TextError::OutOfBounds`] instead of panicking when
+/// `idx` is past the end.
+pub fn take_from_array<T: Default, const N: usize>(
+    arr: &mut [T; N],
+    idx: usize,
+) -> Result<T, TextError> {
+    let slot = arr.get_mut(idx).ok_or(TextError::OutOfBounds)?;
+    Ok(std::mem::take(slot))
+}
+
+/// Moves the first element matching `pred` out of `v` and gives it to the caller.
+///
+/// The elements after it shift down to close the gap, keeping their order.
+pub fn remove_first<T>(v: &mut Vec<T>, pred: impl Fn(&T) -> bool) -> Option<T> {
+    let index = v.iter().position(pred)?;
+    Some(v.remove(index))
+}
+
+/// Yields every run of `size` consecutive items as an owned, cloned Vec.
+///
+/// `items.windows(size)` hands out borrowed slices for free. When each window has
+/// to be owned, the clones are made here, one window at a time as the iterator
+/// is advanced, so windows that are never pulled are never cloned. A `size` of 0
+/// yields nothing.
+pub fn owned_windows<T: Clone>(items: &[T], size: usize) -> impl Iterator<Item = Vec<T>> + '_ {
+    (size > 0)
+        .then(|| items.windows(size))
+        .into_iter()
+        .flatten()
+        .map(<[T]>::to_vec)
+}
+
+/// Splits the first whitespace-delimited word off `s`, returning it and the rest.
+///
+/// The word is copied into a new String, but the remainder is `s` itself with
+/// the front drained off, so it keeps `s`'s heap block. Whitespace before the
+/// word and between it and the rest is dropped. If `s` has no word at all, the
+/// result is `None` and an empty String.
+pub fn pop_front_word(mut s: String) -> (Option<String>, String) {
+    let start = s.len() - s.trim_start().len();
+    if start == s.len() {
+        s.clear();
+        return (None, s);
+    }
+
+    let end = s[start..]
+        .find(char::is_whitespace)
+        .map_or(s.len(), |i| start + i);
+    let word = s[start..end].to_string();
+    let rest = end + (s[end..].len() - s[end..].trim_start().len());
+    s.drain(..rest);
+
+    (Some(word), s)
+}
+
+/// Moves every entry of `from` into `into` and returns the merged map.
+///
+/// When both maps have a key, `on_conflict` gets the value from `into` first and
+/// the one from `from` second, and whatever it returns is kept. Keys and values
+/// are moved, never cloned; the only allocation is `into` growing to make room.
+pub fn merge_maps<K: Eq + Hash, V>(
+    mut into: HashMap<K, V>,
+    from: HashMap<K, V>,
+    on_conflict: impl Fn(V, V) -> V,
+) -> HashMap<K, V> {
+    into.reserve(from.len());
+    for (key, value) in from {
+        match into.entry(key) {
+            Entry::Vacant(e) => {
+                e.insert(value);
+            }
+            // on_conflict needs the old value by value, so it comes out and the
+            // merged one goes back in; only a conflict costs a second lookup
+            Entry::Occupied(e) => {
+                let (key, existing) = e.remove_entry();
+                into.insert(key, on_conflict(existing, value));
+            }
+        }
+    }
+    into
+}
+
+/// Moves Strings out of `iter` into a Vec until they would add up to more than
+/// `max_bytes`.
+///
+/// The bool is true if it stopped early. Finding that out means taking the item
+/// that didn't fit out of `iter`, so that one is dropped rather than returned,
+/// and nothing after it is pulled. Every String that is kept is moved, not cloned.
+pub fn collect_up_to(iter: impl Iterator<Item = String>, max_bytes: usize) -> (Vec<String>, bool) {
+    let mut kept = Vec::new();
+    let mut total = 0;
+    for s in iter {
+        if s.len() > max_bytes - total {
+            return (kept, true);
+        }
+        total += s.len();
+        kept.push(s);
+    }
+    (kept, false)
+}
+
+/// Turns the rows of a grid into it's columns, moving every cell to it's new spot.
+///
+/// Cell `rows[i][j]` ends up at `[j][i]`. Shorter rows are padded out to the
+/// longest one with empty Strings, which don't allocate, so every output row is
+/// as long as `rows`. The cells themselves are moved, never cloned; only the
+/// new row Vecs are allocated.
+pub fn transpose(rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut columns: Vec<Vec<String>> =
+        (0..width).map(|_| Vec::with_capacity(rows.len())).collect();
+
+    for row in rows {
+        let len = row.len();
+        for (column, cell) in columns.iter_mut().zip(row) {
+            column.push(cell);
+        }
+        for column in &mut columns[len..] {
+            column.push(String::new());
+        }
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_counted_counts_each_relocation() {
+        let wrapped = MoveCounted::new(String::from("hello"));
+        assert_eq!(wrapped.moves(), 0);
+
+        let wrapped = wrapped.relocate().relocate().relocate();
+        assert_eq!(wrapped.moves(), 3);
+        assert_eq!(wrapped.get(), "hello");
+        assert_eq!(wrapped.into_inner(), "hello");
+    }
+
+    #[test]
+    fn swap_options_moves_the_value_across() {
+        let mut a = Some(String::from("x"));
+        let mut b = None;
+        let heap = a.as_ref().unwrap().as_ptr();
+
+        swap_options(&mut a, &mut b);
+        assert_eq!(a, None);
+        assert_eq!(b.as_deref(), Some("x"));
+        // the same heap bytes, so nothing was cloned
+        assert_eq!(b.as_ref().unwrap().as_ptr(), heap);
+
+        let taken = take_option(&mut b).unwrap();
+        assert_eq!(b, None);
+        assert_eq!(taken.as_ptr(), heap);
+    }
+
+    #[test]
+    fn record_into_parts_moves_both_fields_out() {
+        let record = Record {
+            name: String::from("a"),
+            tags: vec![String::from("x"), String::from("y")],
+        };
+        let name_heap = record.name.as_ptr();
+
+        let (name, tags) = record.into_parts();
+        assert_eq!(name, "a");
+        assert_eq!(tags, ["x", "y"]);
+        assert_eq!(name.as_ptr(), name_heap);
+    }
+
+    #[test]
+    fn move_queue_is_first_in_first_out() {
+        let mut queue = MoveQueue::new();
+        assert!(queue.is_empty());
+        queue.push(String::from("a"));
+        queue.push(String::from("b"));
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.pop().as_deref(), Some("a"));
+        assert_eq!(queue.pop().as_deref(), Some("b"));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn move_queue_never_copies_a_string() {
+        use crate::alloc_count::count_allocations;
+
+        let mut queue = MoveQueue::new();
+        queue.push(String::new());
+        queue.pop(); // gives the queue it's own buffer up front
+
+        let s = String::from("hello");
+        let heap = s.as_ptr();
+        let (popped, n) = count_allocations(|| {
+            queue.push(s);
+            queue.pop().unwrap()
+        });
+        assert_eq!(n, 0);
+        assert_eq!(popped.as_ptr(), heap);
+    }
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn into_chunks_keeps_every_element_in_order() {
+        let chunks = into_chunks(strings(&["a", "b", "c", "d", "e", "f", "g"]), 3);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.iter().map(Vec::len).sum::<usize>(), 7);
+        assert_eq!(
+            chunks,
+            [
+                strings(&["a", "b", "c"]),
+                strings(&["d", "e", "f"]),
+                strings(&["g"])
+            ]
+        );
+    }
+
+    #[test]
+    fn into_chunks_of_an_even_split_has_no_short_chunk() {
+        let chunks = into_chunks(strings(&["a", "b", "c", "d"]), 2);
+        assert_eq!(chunks, [strings(&["a", "b"]), strings(&["c", "d"])]);
+        assert!(into_chunks(Vec::<String>::new(), 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk_size must not be zero")]
+    fn into_chunks_of_size_zero_panics() {
+        into_chunks(strings(&["a"]), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn into_chunks_moves_the_strings() {
+        use crate::alloc_count::count_allocations;
+
+        let v = strings(&["a", "b", "c", "d", "e"]);
+        let heaps: Vec<*const u8> = v.iter().map(|s| s.as_ptr()).collect();
+
+        let (chunks, n) = count_allocations(|| into_chunks(v, 2));
+        // the outer Vec and one Vec per chunk, but not a single String
+        assert_eq!(n, 1 + chunks.len());
+        let moved: Vec<*const u8> = chunks.iter().flatten().map(|s| s.as_ptr()).collect();
+        assert_eq!(moved, heaps);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn into_chunks_of_an_even_split_allocates_no_spare_chunk() {
+        use crate::alloc_count::count_allocations;
+
+        let v = strings(&["a", "b", "c", "d"]);
+        let (chunks, n) = count_allocations(|| into_chunks(v, 2));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(n, 1 + 2, "the outer Vec and exactly one Vec per chunk");
+        assert!(chunks.iter().all(|c| c.capacity() == 2));
+
+        let (chunks, n) = count_allocations(|| into_chunks(Vec::<String>::new(), 2));
+        assert!(chunks.is_empty());
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn merge_sorted_of_overlapping_lists() {
+        let merged = merge_sorted(strings(&["a", "c", "e"]), strings(&["b", "c", "d"]));
+        assert_eq!(merged, strings(&["a", "b", "c", "c", "d", "e"]));
+    }
+
+    #[test]
+    fn merge_sorted_of_disjoint_lists() {
+        assert_eq!(
+            merge_sorted(strings(&["x", "y"]), strings(&["a", "b"])),
+            strings(&["a", "b", "x", "y"])
+        );
+        assert_eq!(merge_sorted(strings(&["a"]), Vec::new()), strings(&["a"]));
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn merge_sorted_moves_the_strings() {
+        use crate::alloc_count::count_allocations;
+
+        let (a, b) = (strings(&["a", "c"]), strings(&["b", "d"]));
+        let mut heaps: Vec<*const u8> = a.iter().chain(&b).map(|s| s.as_ptr()).collect();
+
+        let (merged, n) = count_allocations(|| merge_sorted(a, b));
+        assert_eq!(n, 1, "only the output Vec should be allocated");
+        let mut moved: Vec<*const u8> = merged.iter().map(|s| s.as_ptr()).collect();
+        heaps.sort();
+        moved.sort();
+        assert_eq!(moved, heaps);
+    }
+
+    #[test]
+    fn take_from_array_moves_the_element_out() {
+        let mut arr = [String::from("a"), String::from("b"), String::from("c")];
+        let heap = arr[1].as_ptr();
+
+        let taken = take_from_array(&mut arr, 1).unwrap();
+        assert_eq!(taken, "b");
+        assert_eq!(taken.as_ptr(), heap);
+        assert_eq!(arr, ["a", "", "c"]);
+    }
+
+    #[test]
+    fn take_from_array_past_the_end_is_an_error() {
+        let mut arr = [1, 2, 3];
+        assert_eq!(take_from_array(&mut arr, 3), Err(TextError::OutOfBounds));
+        assert_eq!(arr, [1, 2, 3]);
+    }
+
+    #[test]
+    fn remove_first_hands_back_the_first_match() {
+        let mut v = strings(&["a", "bb", "cc", "d"]);
+        let removed = remove_first(&mut v, |s| s.len() == 2);
+        assert_eq!(removed.as_deref(), Some("bb"));
+        assert_eq!(v.len(), 3);
+        assert_eq!(v, ["a", "cc", "d"]);
+    }
+
+    #[test]
+    fn remove_first_without_a_match_leaves_the_vec_alone() {
+        let mut v = strings(&["a", "b"]);
+        assert_eq!(remove_first(&mut v, |s| s.is_empty()), None);
+        assert_eq!(v.len(), 2);
+    }
+
+    #[test]
+    fn owned_windows_clones_each_run() {
+        let items = strings(&["a", "b", "c"]);
+        let windows: Vec<Vec<String>> = owned_windows(&items, 2).collect();
+        assert_eq!(windows, [strings(&["a", "b"]), strings(&["b", "c"])]);
+        assert_eq!(owned_windows(&items, 0).count(), 0);
+        assert_eq!(owned_windows(&items, 4).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn owned_windows_only_clones_the_windows_pulled() {
+        use crate::alloc_count::count_allocations;
+
+        let items = strings(&["a", "b", "c", "d", "e"]);
+        let (mut windows, n) = count_allocations(|| owned_windows(&items, 2));
+        assert_eq!(n, 0, "nothing is cloned up front");
+
+        let (first, n) = count_allocations(|| windows.next().unwrap());
+        assert_eq!(first, ["a", "b"]);
+        // the window's Vec, and a copy of each of the two Strings in it
+        assert_eq!(n, 3);
+    }
+
+    #[test]
+    fn pop_front_word_splits_off_the_first_word() {
+        let s = String::from("  héllo   big world");
+        let heap = s.as_ptr();
+        let (word, rest) = pop_front_word(s);
+        assert_eq!(word.as_deref(), Some("héllo"));
+        assert_eq!(rest, "big world");
+        // the rest is the same String with the front drained off
+        assert_eq!(rest.as_ptr(), heap);
+    }
+
+    #[test]
+    fn pop_front_word_of_a_single_word_leaves_nothing() {
+        let (word, rest) = pop_front_word(String::from("alone  "));
+        assert_eq!(word.as_deref(), Some("alone"));
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn pop_front_word_without_a_word_is_none() {
+        assert_eq!(pop_front_word(String::from(" \t\n")), (None, String::new()));
+        assert_eq!(pop_front_word(String::new()), (None, String::new()));
+    }
+
+    #[test]
+    fn merge_maps_keeps_every_key() {
+        let into = HashMap::from([("a", 1), ("b", 2)]);
+        let from = HashMap::from([("b", 10), ("c", 3)]);
+        let merged = merge_maps(into, from, |old, new| old + new);
+        assert_eq!(merged, HashMap::from([("a", 1), ("b", 12), ("c", 3)]));
+    }
+
+    #[test]
+    fn merge_maps_passes_the_into_value_first() {
+        let into = HashMap::from([("k", String::from("old"))]);
+        let from = HashMap::from([("k", String::from("new"))]);
+        let merged = merge_maps(into, from, |first, second| format!("{first}+{second}"));
+        assert_eq!(merged["k"], "old+new");
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn merge_maps_moves_without_cloning() {
+        use crate::alloc_count::count_allocations;
+
+        let mut into = HashMap::with_capacity(8);
+        into.insert(String::from("a"), String::from("1"));
+        into.insert(String::from("b"), String::from("2"));
+        let mut from = HashMap::new();
+        from.insert(String::from("b"), String::from("20"));
+        from.insert(String::from("c"), String::from("3"));
+
+        // keep the bigger value, so on_conflict allocates nothing either
+        let (merged, n) = count_allocations(|| {
+            merge_maps(
+                into,
+                from,
+                |old, new| if new.len() > old.len() { new } else { old },
+            )
+        });
+        assert_eq!(n, 0, "into had room, so nothing should have been allocated");
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged["b"], "20");
+    }
+
+    #[test]
+    fn collect_up_to_within_the_limit_keeps_everything() {
+        let (kept, stopped) = collect_up_to(strings(&["ab", "cd"]).into_iter(), 4);
+        assert_eq!(kept, ["ab", "cd"]);
+        assert!(!stopped);
+        assert_eq!(collect_up_to(std::iter::empty(), 0), (Vec::new(), false));
+    }
+
+    #[test]
+    fn collect_up_to_stops_at_the_first_string_over_the_limit() {
+        let mut iter = strings(&["ab", "cde", "f", "g"]).into_iter();
+        let (kept, stopped) = collect_up_to(iter.by_ref(), 4);
+        assert_eq!(kept, ["ab"]);
+        assert!(stopped);
+        // "cde" was taken and dropped; nothing after it was pulled
+        assert_eq!(iter.collect::<Vec<_>>(), ["f", "g"]);
+    }
+
+    #[test]
+    fn collect_up_to_moves_the_strings_it_keeps() {
+        let s = String::from("moved");
+        let heap = s.as_ptr();
+        let (kept, _) = collect_up_to(std::iter::once(s), 10);
+        assert_eq!(kept[0].as_ptr(), heap);
+    }
+
+    #[test]
+    fn transpose_turns_rows_into_columns() {
+        let rows = vec![strings(&["a", "b", "c"]), strings(&["d", "e", "f"])];
+        let heap = rows[1][0].as_ptr();
+
+        let columns = transpose(rows);
+        assert_eq!(
+            columns,
+            [
+                strings(&["a", "d"]),
+                strings(&["b", "e"]),
+                strings(&["c", "f"])
+            ]
+        );
+        assert_eq!(columns[0][1].as_ptr(), heap);
+    }
+
+    #[test]
+    fn transpose_pads_short_rows() {
+        let rows = vec![strings(&["a"]), strings(&["b", "c", "d"]), Vec::new()];
+        assert_eq!(
+            transpose(rows),
+            [
+                strings(&["a", "b", ""]),
+                strings(&["", "c", ""]),
+                strings(&["", "d", ""])
+            ]
+        );
+        assert!(transpose(Vec::new()).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn transpose_only_allocates_the_new_rows() {
+        use crate::alloc_count::count_allocations;
+
+        let rows = vec![strings(&["a", "b", "c"]), strings(&["d"])];
+        let (columns, n) = count_allocations(|| transpose(rows));
+        // the outer Vec and one Vec per column; no cell, padding included, is new
+        assert_eq!(n, 1 + 3);
+        assert_eq!(columns[2], ["c", ""]);
+    }
+
+    #[test]
+    fn move_counted_into_inner_hands_back_the_same_value() {
+        let s = String::from("hello");
+        let heap = s.as_ptr();
+        let inner = MoveCounted::new(s).relocate().into_inner();
+        assert_eq!(inner.as_ptr(), heap);
+    }
+}
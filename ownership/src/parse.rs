@@ -0,0 +1,586 @@
+/*
+
+Parsing by borrowing
+
+Most parsing doesn't need to copy any text. The pieces we're after are already in
+the input, so we can hand back slices that point into it. The slices borrow the
+input, so they can't outlive it, and no allocation happens at all.
+
+*/
+
+use std::ops::Range;
+
+/// Splits `line` on it's first `=` into a trimmed key and value.
+///
+/// Both halves borrow from `line`. Any later `=` belongs to the value. Returns
+/// `None` if there is no `=`.
+pub fn parse_kv(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// A whitespace-delimited piece of some input, and where it sits in that input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    /// Byte offset of the first byte of `text`.
+    pub start: usize,
+    /// Byte offset just past the last byte of `text`.
+    pub end: usize,
+}
+
+/// Splits `s` on whitespace, recording each token's byte span.
+///
+/// `&s[tok.start..tok.end]` is always `tok.text`.
+pub fn tokenize_with_spans(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (i, c) in s.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(st)) => {
+                tokens.push(Token {
+                    text: &s[st..i],
+                    start: st,
+                    end: i,
+                });
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(st) = start {
+        tokens.push(Token {
+            text: &s[st..],
+            start: st,
+            end: s.len(),
+        });
+    }
+
+    tokens
+}
+
+/*
+
+A struct that holds a borrow
+
+A struct can hold a reference too, as long as we tell the compiler how long it is
+allowed to live. Parser<'a> says: this parser borrows a &str that lives for 'a, so
+the parser can't outlive that text. Anything it hands out from the source can be
+given the same 'a, which means it stays valid even after the parser itself is gone.
+
+*/
+
+#[derive(Debug, Clone)]
+pub struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Parser { source, pos: 0 }
+    }
+
+    /// The part of the source not consumed yet. It borrows from the source, not
+    /// from the parser.
+    pub fn remaining(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    /// Moves forward by `n` chars, stopping at the end of the source.
+    pub fn advance(&mut self, n: usize) {
+        let rest = self.remaining();
+        self.pos += rest.char_indices().nth(n).map_or(rest.len(), |(i, _)| i);
+    }
+
+    /// How many bytes of the source have been consumed.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+}
+
+/// Checks that `s` is an ASCII identifier: a letter or `_`, then letters, digits
+/// or `_`.
+///
+/// A valid identifier is handed back as the same borrowed slice, so the common case
+/// costs nothing. Only a failure allocates, to build a message naming the first
+/// bad char and it's position (in chars, from 0).
+pub fn validate_identifier(s: &str) -> Result<&str, String> {
+    if s.is_empty() {
+        return Err(String::from("identifier is empty"));
+    }
+
+    for (i, c) in s.chars().enumerate() {
+        let ok = c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit());
+        if !ok {
+            return Err(format!("invalid character {c:?} at position {i}"));
+        }
+    }
+
+    Ok(s)
+}
+
+/// Returns how many bytes of whitespace `s` starts with.
+pub fn leading_whitespace_len(s: &str) -> usize {
+    s.len() - s.trim_start().len()
+}
+
+/// Borrows the whitespace `s` starts with, such as a line's indentation.
+pub fn indentation(s: &str) -> &str {
+    &s[..leading_whitespace_len(s)]
+}
+
+/// Returns the longest prefix that every string in `strings` starts with, borrowed
+/// from the first one. An empty slice gives "".
+///
+/// The prefix always ends on a char boundary, so strings that share only the first
+/// byte of a multibyte char don't count as sharing that char.
+pub fn longest_common_prefix<'a>(strings: &'a [&str]) -> &'a str {
+    let Some((first, rest)) = strings.split_first() else {
+        return "";
+    };
+
+    let mut len = first.len();
+    for s in rest {
+        len = first
+            .bytes()
+            .zip(s.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count();
+    }
+    while !first.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    &first[..len]
+}
+
+/// Finds the byte ranges of the sentences in `s`.
+///
+/// A sentence ends with `.`, `!` or `?` followed by whitespace or the end of the
+/// text, and it's range includes that punctuation but not the whitespace around
+/// it. Text after the last such mark counts as one more sentence.
+pub fn sentence_spans(s: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let at_break = matches!(c, '.' | '!' | '?')
+            && chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        if at_break {
+            push_trimmed(s, start..i + 1, &mut spans);
+            start = i + 1;
+        }
+    }
+    push_trimmed(s, start..s.len(), &mut spans);
+
+    spans
+}
+
+// pushes `range` with surrounding whitespace cut off, unless nothing is left
+fn push_trimmed(s: &str, range: Range<usize>, spans: &mut Vec<Range<usize>>) {
+    let piece = &s[range.clone()];
+    let start = range.start + leading_whitespace_len(piece);
+    let end = range.start + piece.trim_end().len();
+    if start < end {
+        spans.push(start..end);
+    }
+}
+
+/// Splits `s` into sentences, borrowing each one from `s`. See [`sentence_spans`]
+/// for where sentences end.
+///
+/// There is no list of abbreviations, so "Dr. Smith is in." comes out as two
+/// sentences, "Dr." and "Smith is in.". A mark not followed by whitespace, as in
+/// "3.14", doesn't end a sentence.
+pub fn sentences(s: &str) -> Vec<&str> {
+    sentence_spans(s).into_iter().map(|r| &s[r]).collect()
+}
+
+/// Borrows the text between the first `open` in `s` and the first `close` after it.
+///
+/// Returns `None` if either delimiter is missing. Nesting isn't tracked, so in
+/// `"[a[b]c]"` the text between `"["` and `"]"` is `"a[b"`. The slice always borrows
+/// from `s`, never from the delimiters.
+pub fn between<'a>(s: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let (_, after_open) = s.split_once(open)?;
+    let (inside, _) = after_open.split_once(close)?;
+    Some(inside)
+}
+
+/// Lazily yields the chars of `s` that `pred` accepts.
+///
+/// Nothing is collected: each char is decoded from `s` and tested only when the
+/// iterator is advanced, so the iterator borrows `s` (and owns `pred`) for as
+/// long as it lives.
+pub fn chars_where<'a>(
+    s: &'a str,
+    pred: impl Fn(char) -> bool + 'a,
+) -> impl Iterator<Item = char> + 'a {
+    s.chars().filter(move |&c| pred(c))
+}
+
+/// Splits `s` around the first `delim`, borrowing both halves from `s`.
+///
+/// The delimiter itself is in neither half, even when it's a multibyte char.
+/// Returns `None` if `s` has no `delim`.
+pub fn split_once_ref(s: &str, delim: char) -> Option<(&str, &str)> {
+    let at = s.find(delim)?;
+    Some((&s[..at], &s[at + delim.len_utf8()..]))
+}
+
+/// Borrows what's left of `s` after `prefix`, or returns `None` if `s` doesn't
+/// start with it.
+///
+/// A match always covers whole chars of `s`, since `prefix` is a whole str
+/// too, so the cut can't land inside a multibyte char.
+pub fn strip_prefix_ref<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.strip_prefix(prefix)
+}
+
+/// Borrows what's left of `s` before `suffix`, or returns `None` if `s` doesn't
+/// end with it. See [`strip_prefix_ref`].
+pub fn strip_suffix_ref<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    s.strip_suffix(suffix)
+}
+
+/// Splits one line of CSV on it's commas, borrowing each field from `line`.
+///
+/// A field that starts with `"` runs to the closing `"`, commas included, and is
+/// returned without the quotes around it. A doubled `""` inside stands for one
+/// quote in CSV, but turning it into one would mean building a new String, so it
+/// is left doubled in the borrowed slice. Anything after a closing quote and
+/// before the next comma is skipped. An empty line is one empty field, and a
+/// trailing comma ends the line with another one.
+pub fn csv_fields(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(line);
+    std::iter::from_fn(move || {
+        let s = rest?;
+        let (field, after) = match s.strip_prefix('"') {
+            Some(quoted) => {
+                let end = closing_quote(quoted);
+                let after = quoted.get(end + 1..).unwrap_or("");
+                (&quoted[..end], after.find(',').map(|i| &after[i + 1..]))
+            }
+            None => match s.split_once(',') {
+                Some((field, after)) => (field, Some(after)),
+                None => (s, None),
+            },
+        };
+        rest = after;
+        Some(field)
+    })
+}
+
+// byte offset of the quote that closes a quoted field, skipping doubled quotes; if
+// the field is never closed it runs to the end
+fn closing_quote(quoted: &str) -> usize {
+    let bytes = quoted.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            if bytes.get(i + 1) != Some(&b'"') {
+                return i;
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kv_splits_a_simple_pair() {
+        assert_eq!(parse_kv("a=b"), Some(("a", "b")));
+    }
+
+    #[test]
+    fn parse_kv_trims_both_sides() {
+        assert_eq!(parse_kv("  key = value  "), Some(("key", "value")));
+    }
+
+    #[test]
+    fn parse_kv_splits_on_the_first_equals_only() {
+        assert_eq!(parse_kv("url=a=b=c"), Some(("url", "a=b=c")));
+    }
+
+    #[test]
+    fn parse_kv_without_equals_is_none() {
+        assert_eq!(parse_kv("no pair here"), None);
+    }
+
+    #[test]
+    fn tokenize_with_spans_skips_surrounding_whitespace() {
+        let s = "  ab  c ";
+        let tokens = tokenize_with_spans(s);
+        assert_eq!(
+            tokens,
+            [
+                Token {
+                    text: "ab",
+                    start: 2,
+                    end: 4
+                },
+                Token {
+                    text: "c",
+                    start: 6,
+                    end: 7
+                },
+            ]
+        );
+        assert!(tokenize_with_spans(" \t\n").is_empty());
+    }
+
+    #[test]
+    fn tokenize_with_spans_of_multibyte_tokens() {
+        let s = "héllo 🦀 wörld";
+        let tokens = tokenize_with_spans(s);
+        assert_eq!(tokens.len(), 3);
+        assert_eq!((tokens[1].start, tokens[1].end), (7, 11));
+        for tok in tokens {
+            assert_eq!(&s[tok.start..tok.end], tok.text);
+        }
+    }
+
+    #[test]
+    fn parser_advances_over_chars() {
+        let source = String::from("héllo world");
+        let rest;
+        {
+            let mut parser = Parser::new(&source);
+            parser.advance(2);
+            assert_eq!(parser.pos(), 3);
+            assert_eq!(parser.remaining(), "llo world");
+            parser.advance(4);
+            rest = parser.remaining();
+        } // the parser is gone, but rest borrows from source
+        assert_eq!(rest, "world");
+    }
+
+    #[test]
+    fn parser_stops_at_the_end() {
+        let mut parser = Parser::new("ab");
+        parser.advance(10);
+        assert_eq!(parser.remaining(), "");
+        assert_eq!(parser.pos(), 2);
+    }
+
+    #[test]
+    fn validate_identifier_hands_back_the_input() {
+        let name = String::from("_count2");
+        let ok = validate_identifier(&name).unwrap();
+        assert_eq!(ok, "_count2");
+        assert_eq!(ok.as_ptr(), name.as_ptr());
+    }
+
+    #[test]
+    fn validate_identifier_names_the_bad_char_and_its_position() {
+        assert_eq!(
+            validate_identifier("2fast"),
+            Err(String::from("invalid character '2' at position 0"))
+        );
+        assert_eq!(
+            validate_identifier("naïve"),
+            Err(String::from("invalid character 'ï' at position 2"))
+        );
+        assert_eq!(
+            validate_identifier(""),
+            Err(String::from("identifier is empty"))
+        );
+    }
+
+    #[test]
+    fn no_leading_whitespace() {
+        assert_eq!(leading_whitespace_len("code  "), 0);
+        assert_eq!(indentation("code  "), "");
+        assert_eq!(indentation(""), "");
+    }
+
+    #[test]
+    fn a_line_of_only_whitespace_is_all_indentation() {
+        assert_eq!(leading_whitespace_len(" \t "), 3);
+        assert_eq!(indentation(" \t "), " \t ");
+    }
+
+    #[test]
+    fn mixed_tabs_and_spaces() {
+        let line = String::from("\t  \tlet x = 1;");
+        assert_eq!(leading_whitespace_len(&line), 4);
+        let indent = indentation(&line);
+        assert_eq!(indent, "\t  \t");
+        assert_eq!(indent.as_ptr(), line.as_ptr());
+    }
+
+    #[test]
+    fn longest_common_prefix_of_several() {
+        assert_eq!(longest_common_prefix(&["flower", "flow", "flight"]), "fl");
+    }
+
+    #[test]
+    fn longest_common_prefix_of_one_is_itself() {
+        assert_eq!(longest_common_prefix(&["alone"]), "alone");
+        assert_eq!(longest_common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn longest_common_prefix_with_nothing_shared() {
+        assert_eq!(longest_common_prefix(&["dog", "racecar", "car"]), "");
+        // 'é' and 'è' share their first byte, but not the whole char
+        assert_eq!(longest_common_prefix(&["caé", "caè"]), "ca");
+    }
+
+    #[test]
+    fn sentences_end_at_marks_before_whitespace() {
+        assert_eq!(sentences("Hi. Bye!"), ["Hi.", "Bye!"]);
+        assert_eq!(
+            sentences("Pi is 3.14, roughly.  Is it?"),
+            ["Pi is 3.14, roughly.", "Is it?"]
+        );
+    }
+
+    #[test]
+    fn sentences_split_after_an_abbreviation() {
+        assert_eq!(sentences("Dr. Smith is in."), ["Dr.", "Smith is in."]);
+    }
+
+    #[test]
+    fn sentences_of_nothing() {
+        assert!(sentences("").is_empty());
+        assert!(sentences("  \n ").is_empty());
+        assert_eq!(sentences("no mark at the end"), ["no mark at the end"]);
+    }
+
+    #[test]
+    fn between_finds_the_text_inside() {
+        let s = String::from("key=[value] rest");
+        let inside = between(&s, "[", "]").unwrap();
+        assert_eq!(inside, "value");
+        assert!(s.as_bytes().as_ptr_range().contains(&inside.as_ptr()));
+    }
+
+    #[test]
+    fn between_with_a_missing_delimiter_is_none() {
+        assert_eq!(between("no brackets", "[", "]"), None);
+        assert_eq!(between("[open only", "[", "]"), None);
+        assert_eq!(between("close] before [", "[", "]"), None);
+    }
+
+    #[test]
+    fn between_does_not_track_nesting() {
+        assert_eq!(between("[a[b]c]", "[", "]"), Some("a[b"));
+    }
+
+    #[test]
+    fn between_multi_char_delimiters() {
+        assert_eq!(between("<!-- é -->", "<!--", "-->"), Some(" é "));
+        assert_eq!(between("{{}}", "{{", "}}"), Some(""));
+    }
+
+    #[test]
+    fn chars_where_keeps_the_accepted_chars() {
+        let digits: String = chars_where("a1b2", |c| c.is_ascii_digit()).collect();
+        assert_eq!(digits, "12");
+        let accented: String = chars_where("crème brûlée", |c| !c.is_ascii()).collect();
+        assert_eq!(accented, "èûé");
+    }
+
+    #[test]
+    fn chars_where_only_tests_chars_as_they_are_pulled() {
+        let calls = std::cell::Cell::new(0);
+        let mut letters = chars_where("ab1cd", |c| {
+            calls.set(calls.get() + 1);
+            c.is_alphabetic()
+        });
+        assert_eq!(calls.get(), 0);
+
+        assert_eq!(letters.next(), Some('a'));
+        assert_eq!(calls.get(), 1);
+        assert_eq!(letters.nth(1), Some('c'));
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn split_once_ref_at_the_first_delimiter() {
+        assert_eq!(split_once_ref("k=v=w", '='), Some(("k", "v=w")));
+    }
+
+    #[test]
+    fn split_once_ref_around_a_multibyte_delimiter() {
+        assert_eq!(split_once_ref("a→b", '→'), Some(("a", "b")));
+    }
+
+    #[test]
+    fn split_once_ref_at_either_end() {
+        assert_eq!(split_once_ref("=v", '='), Some(("", "v")));
+        assert_eq!(split_once_ref("k=", '='), Some(("k", "")));
+    }
+
+    #[test]
+    fn split_once_ref_without_the_delimiter_is_none() {
+        assert_eq!(split_once_ref("no delimiter", '='), None);
+        let s = String::from("left:right");
+        let (left, right) = split_once_ref(&s, ':').unwrap();
+        assert_eq!(left.as_ptr(), s.as_ptr());
+        assert!(s.as_bytes().as_ptr_range().contains(&right.as_ptr()));
+    }
+
+    #[test]
+    fn strip_prefix_ref_borrows_the_rest() {
+        let s = String::from("récipe: soup");
+        let rest = strip_prefix_ref(&s, "récipe: ").unwrap();
+        assert_eq!(rest, "soup");
+        assert!(s.as_bytes().as_ptr_range().contains(&rest.as_ptr()));
+        assert_eq!(strip_prefix_ref("abc", ""), Some("abc"));
+    }
+
+    #[test]
+    fn strip_prefix_ref_without_the_prefix_is_none() {
+        assert_eq!(strip_prefix_ref("soup", "récipe"), None);
+        assert_eq!(strip_prefix_ref("ab", "abc"), None);
+    }
+
+    #[test]
+    fn strip_suffix_ref_borrows_the_rest() {
+        let s = String::from("report.tär");
+        let rest = strip_suffix_ref(&s, ".tär").unwrap();
+        assert_eq!(rest, "report");
+        assert_eq!(rest.as_ptr(), s.as_ptr());
+        assert_eq!(strip_suffix_ref("abc", "abc"), Some(""));
+    }
+
+    #[test]
+    fn strip_suffix_ref_without_the_suffix_is_none() {
+        assert_eq!(strip_suffix_ref("report.txt", ".tär"), None);
+    }
+
+    #[test]
+    fn csv_fields_of_a_plain_line() {
+        let line = String::from("a,b,c");
+        let fields: Vec<&str> = csv_fields(&line).collect();
+        assert_eq!(fields, ["a", "b", "c"]);
+        assert_eq!(fields[0].as_ptr(), line.as_ptr());
+    }
+
+    #[test]
+    fn csv_fields_keeps_empty_fields() {
+        assert_eq!(csv_fields("a,,c").collect::<Vec<_>>(), ["a", "", "c"]);
+        assert_eq!(csv_fields("a,b,").collect::<Vec<_>>(), ["a", "b", ""]);
+        assert_eq!(csv_fields("").collect::<Vec<_>>(), [""]);
+    }
+
+    #[test]
+    fn csv_fields_with_quotes() {
+        assert_eq!(
+            csv_fields(r#""x,y",z,"say ""hi""""#).collect::<Vec<_>>(),
+            ["x,y", "z", r#"say ""hi"""#]
+        );
+    }
+}
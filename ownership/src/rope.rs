@@ -0,0 +1,96 @@
+/*
+
+Ropes
+
+Joining two Strings with push_str copies the second one's bytes onto the end of the
+first, and may have to move the first one to a bigger block as well. When large
+pieces of text get joined over and over, most of the time goes to copying heap data
+around.
+
+A rope keeps each piece as it's own owned String and links the pieces together
+instead. Joining two ropes only relinks them, and no text is copied until we ask for
+the whole thing as one String.
+
+*/
+
+use std::collections::LinkedList;
+use std::fmt;
+
+/// Text made of owned segments that can be joined without copying them.
+#[derive(Debug, Clone, Default)]
+pub struct Rope {
+    segments: LinkedList<String>,
+    len: usize,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Joins `other` onto the end of this rope in O(1), taking ownership of both.
+    pub fn concat(mut self, mut other: Rope) -> Rope {
+        self.segments.append(&mut other.segments);
+        self.len += other.len;
+        self
+    }
+
+    /// Length in bytes, tracked as segments are added rather than recounted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<String> for Rope {
+    fn from(s: String) -> Self {
+        let len = s.len();
+        let mut segments = LinkedList::new();
+        segments.push_back(s);
+        Rope { segments, len }
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(s: &str) -> Self {
+        Rope::from(s.to_string())
+    }
+}
+
+// gives us to_string(), which copies every segment into one new String
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            f.write_str(segment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rope_of_many_pieces_matches_naive_concatenation() {
+        let pieces: Vec<String> = (0..200).map(|i| format!("{i},é ")).collect();
+
+        let rope = pieces.iter().fold(Rope::new(), |rope, piece| {
+            rope.concat(Rope::from(piece.as_str()))
+        });
+        let naive: String = pieces.concat();
+
+        assert_eq!(rope.len(), naive.len());
+        assert_eq!(rope.to_string(), naive);
+    }
+
+    #[test]
+    fn empty_rope() {
+        let rope = Rope::new().concat(Rope::from(""));
+        assert!(rope.is_empty());
+        assert_eq!(rope.to_string(), "");
+    }
+}
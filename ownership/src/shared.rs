@@ -0,0 +1,66 @@
+/*
+
+Shared ownership
+
+An Rc lets several owners share one value; it is dropped when the last owner goes
+away. Because the value is shared, an Rc only hands out shared borrows. To change
+it, we either need to be the only owner, or we make our own copy first and change
+that, leaving the other owners' value alone. This is called copy-on-write.
+
+*/
+
+use std::rc::Rc;
+
+/// Returns a unique borrow of the String behind `s`, cloning it first only if
+/// other owners share it.
+///
+/// With one owner no clone happens. With more, `s` is pointed at a fresh copy and
+/// the other owners keep the original, unchanged.
+pub fn make_unique(s: &mut Rc<String>) -> &mut String {
+    Rc::make_mut(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_unique_with_one_owner_changes_the_value_in_place() {
+        let mut s = Rc::new(String::from("solo"));
+        let block = Rc::as_ptr(&s);
+        make_unique(&mut s).push('!');
+        assert_eq!(*s, "solo!");
+        // still the same Rc block, so nothing was cloned
+        assert_eq!(Rc::as_ptr(&s), block);
+        assert_eq!(Rc::strong_count(&s), 1);
+    }
+
+    #[test]
+    fn make_unique_with_two_owners_leaves_the_other_alone() {
+        let mut s = Rc::new(String::from("shared"));
+        let other = Rc::clone(&s);
+
+        make_unique(&mut s).push('!');
+        assert_eq!(*s, "shared!");
+        assert_eq!(*other, "shared");
+        assert!(!Rc::ptr_eq(&s, &other));
+        assert_eq!(Rc::strong_count(&other), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn make_unique_clones_only_when_shared() {
+        use crate::alloc_count::count_allocations;
+
+        let mut s = Rc::new(String::from("text"));
+        let (_, n) = count_allocations(|| make_unique(&mut s).clear());
+        assert_eq!(n, 0);
+
+        s = Rc::new(String::from("text"));
+        let other = Rc::clone(&s);
+        let (_, n) = count_allocations(|| make_unique(&mut s).clear());
+        // one clone: a new Rc block, and the String's copy of the text
+        assert_eq!(n, 2);
+        assert_eq!(*other, "text");
+    }
+}
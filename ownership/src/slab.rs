@@ -0,0 +1,119 @@
+/*
+
+Handles instead of references
+
+A reference into a collection stops the collection from changing while the
+reference is alive; the compiler sees to that. Sometimes we need to hold on to
+something longer, so we keep an index instead. An index isn't checked by the
+compiler, though: if the slot it points to is freed and reused, the old index
+silently refers to someone else's value.
+
+Storing a generation number in every slot, and bumping it whenever the slot is
+freed, lets us catch that at runtime. A handle remembers the generation it was
+created with, and a mismatch means it is stale.
+
+*/
+
+/// Refers to a String stored in a [`Slab`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    pub index: usize,
+    pub generation: u32,
+}
+
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    value: Option<String>,
+}
+
+/// Owns Strings and hands out [`Handle`]s to them that notice when they go stale.
+#[derive(Debug, Default)]
+pub struct Slab {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+impl Slab {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes ownership of `s` and returns a handle to it, reusing a freed slot if
+    /// there is one.
+    pub fn insert(&mut self, s: String) -> Handle {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index];
+            slot.value = Some(s);
+            return Handle {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(s),
+        });
+        Handle {
+            index: self.slots.len() - 1,
+            generation: 0,
+        }
+    }
+
+    /// Borrows the String behind `h`, or returns `None` if it has been removed.
+    pub fn get(&self, h: Handle) -> Option<&str> {
+        let slot = self.slots.get(h.index)?;
+        if slot.generation != h.generation {
+            return None;
+        }
+        slot.value.as_deref()
+    }
+
+    /// Moves the String behind `h` back out to the caller and frees it's slot.
+    /// Every existing handle to the slot goes stale.
+    pub fn remove(&mut self, h: Handle) -> Option<String> {
+        let slot = self.slots.get_mut(h.index)?;
+        if slot.generation != h.generation {
+            return None;
+        }
+
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(h.index);
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_stale_handle_resolves_to_none() {
+        let mut slab = Slab::new();
+        let first = slab.insert(String::from("first"));
+        assert_eq!(slab.get(first), Some("first"));
+
+        assert_eq!(slab.remove(first).as_deref(), Some("first"));
+        assert_eq!(slab.get(first), None);
+        assert!(slab.is_empty());
+
+        // the freed slot is reused, under a new generation
+        let second = slab.insert(String::from("second"));
+        assert_eq!(second.index, first.index);
+        assert_ne!(second, first);
+        assert_eq!(slab.get(first), None);
+        assert_eq!(slab.remove(first), None);
+        assert_eq!(slab.get(second), Some("second"));
+        assert_eq!(slab.len(), 1);
+    }
+}
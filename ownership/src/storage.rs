@@ -0,0 +1,355 @@
+/*
+
+Stack and heap
+
+Values whose size is known at compile time live on the stack. Types like String
+keep a small, fixed-size part on the stack (pointer, length, capacity) and put
+their contents on the heap. The fixed-size part is all that size_of reports.
+
+Whether a type is Copy can't be asked of a generic T directly, but a type that
+needs to run drop code can never be Copy, so needs_drop gives us a good guess.
+
+*/
+
+use std::mem;
+
+use crate::drops::Loud;
+
+/// Values at or below this many bytes count as small.
+pub const SMALL_SIZE: usize = 2 * mem::size_of::<usize>();
+
+/// Guesses whether `T` behaves like a Copy type.
+///
+/// Copy types never need drop code, so this returns false for every type that
+/// owns heap memory. It can return true for a few non-Copy types too, such as
+/// `&mut T` or a struct of plain numbers that just doesn't derive Copy.
+pub fn describes_copy<T>() -> bool {
+    !mem::needs_drop::<T>()
+}
+
+/// What [`stack_vs_heap_report`] found out about a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageReport {
+    /// Bytes the value takes up where it is stored, not counting heap data.
+    pub size: usize,
+    pub align: usize,
+    /// See [`describes_copy`].
+    pub copy_like: bool,
+    /// True if the type runs drop code, which usually means it owns heap memory.
+    pub heap_backed: bool,
+    /// Small and Copy-like: it lives entirely on the stack and is cheap to copy.
+    pub stack_only: bool,
+}
+
+/// Reports how a value of type `T` is stored.
+pub fn stack_vs_heap_report<T>(value: &T) -> StorageReport {
+    let size = mem::size_of_val(value);
+    let copy_like = describes_copy::<T>();
+
+    StorageReport {
+        size,
+        align: mem::align_of_val(value),
+        copy_like,
+        heap_backed: mem::needs_drop::<T>(),
+        stack_only: copy_like && size <= SMALL_SIZE,
+    }
+}
+
+/*
+
+Borrowed and owned copies side by side
+
+A &str is a pointer and a length pointing at bytes someone else owns; cloning it
+into a String copies those bytes into a new heap block. Comparing addresses shows
+the difference: the borrow points at the original bytes, the clone somewhere else.
+
+*/
+
+/// Returns `s` itself together with a freshly allocated copy of it.
+pub fn borrow_and_own(s: &str) -> (&str, String) {
+    (s, s.to_string())
+}
+
+/// Checks whether two slices point into overlapping memory.
+///
+/// Empty slices cover no bytes, so they never overlap anything.
+pub fn same_allocation(a: &str, b: &str) -> bool {
+    let a = a.as_bytes().as_ptr_range();
+    let b = b.as_bytes().as_ptr_range();
+    a.start < b.end && b.start < a.end
+}
+
+/*
+
+Small strings on the stack
+
+A String always puts it's bytes on the heap, even for a two-letter word. A String
+is already 24 bytes on the stack (on 64-bit platforms), so a type of about the
+same size can keep short text right there instead, and only fall back to a heap
+String when the text doesn't fit.
+
+*/
+
+/// How many bytes [`SmallStr`] keeps inline.
+pub const INLINE_CAP: usize = 23;
+
+/// Text that lives on the stack when it is at most [`INLINE_CAP`] bytes, and in a
+/// heap-allocated String otherwise.
+#[derive(Debug, Clone)]
+pub enum SmallStr {
+    Inline { buf: [u8; INLINE_CAP], len: u8 },
+    Heap(String),
+}
+
+impl SmallStr {
+    /// Copies `s` inline if it fits; otherwise allocates a String for it.
+    pub fn new(s: &str) -> Self {
+        if s.len() <= INLINE_CAP {
+            let mut buf = [0u8; INLINE_CAP];
+            buf[..s.len()].copy_from_slice(s.as_bytes());
+            SmallStr::Inline {
+                buf,
+                len: s.len() as u8,
+            }
+        } else {
+            SmallStr::Heap(s.to_string())
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            // the bytes were copied from a &str, so they are valid UTF-8
+            SmallStr::Inline { buf, len } => std::str::from_utf8(&buf[..*len as usize]).unwrap(),
+            SmallStr::Heap(s) => s,
+        }
+    }
+
+    /// True if the text is stored inline, with no heap allocation.
+    pub fn is_inline(&self) -> bool {
+        matches!(self, SmallStr::Inline { .. })
+    }
+}
+
+impl From<&str> for SmallStr {
+    fn from(s: &str) -> Self {
+        SmallStr::new(s)
+    }
+}
+
+/*
+
+Moving into and out of a Box
+
+Box::new moves a value onto the heap and gives us an owning pointer to it. Moving
+out with *boxed copies the value back to the stack and frees the Box's heap block.
+For a String, only the pointer, length and capacity make that trip; the text it
+points to stays where it is.
+
+*/
+
+/// Moves `value` onto the heap.
+pub fn into_boxed<T>(value: T) -> Box<T> {
+    Box::new(value)
+}
+
+/// Moves the value out of `b`, freeing the Box itself.
+#[allow(clippy::boxed_local)] // taking a Box by value is the point here
+pub fn out_of_box<T>(b: Box<T>) -> T {
+    *b
+}
+
+/// Moves `value` into a Box and straight back out again.
+pub fn box_and_unbox<T>(value: T) -> T {
+    out_of_box(into_boxed(value))
+}
+
+/*
+
+Boxes of different types
+
+A Vec can only hold one type, but Box<dyn Describable> is one type no matter what
+is inside the box. Each box owns a value of it's own type on the heap, and a Vec of
+them owns all the boxes, so dropping the Vec drops every value, each with it's own
+drop code.
+
+*/
+
+/// Something that can describe itself in a line of text.
+pub trait Describable {
+    fn describe(&self) -> String;
+}
+
+impl Describable for String {
+    fn describe(&self) -> String {
+        format!("a String of {} bytes", self.len())
+    }
+}
+
+impl Describable for i32 {
+    fn describe(&self) -> String {
+        format!("the number {self}")
+    }
+}
+
+impl Describable for Loud {
+    fn describe(&self) -> String {
+        format!("a Loud named {}", self.name)
+    }
+}
+
+/// Describes each item, in order, and drops it straight after.
+///
+/// `items` is consumed, so the boxes are gone once this returns; only the
+/// descriptions are left.
+pub fn describe_all(items: Vec<Box<dyn Describable>>) -> Vec<String> {
+    items.into_iter().map(|item| item.describe()).collect()
+}
+
+/// How a String's memory splits between the stack and the heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloneBreakdown {
+    /// The pointer, length and capacity: what a move copies.
+    pub stack_bytes: usize,
+    /// The heap block the String owns: what a clone has to copy as well.
+    pub heap_bytes: usize,
+}
+
+/// Splits the memory of `s` into the part a move copies and the part only a clone
+/// copies.
+///
+/// `heap_bytes` is the whole capacity. A clone only copies the `len` bytes in use,
+/// into a block of about that size, so clones of a String with spare capacity
+/// come out smaller than the original.
+pub fn clone_breakdown(s: &String) -> CloneBreakdown {
+    CloneBreakdown {
+        stack_bytes: mem::size_of::<String>(),
+        heap_bytes: s.capacity(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_i32_is_small_and_copy() {
+        let report = stack_vs_heap_report(&5i32);
+        assert_eq!(report.size, 4);
+        assert_eq!(report.align, 4);
+        assert!(report.copy_like);
+        assert!(!report.heap_backed);
+        assert!(report.stack_only);
+    }
+
+    #[test]
+    fn a_string_is_heap_backed() {
+        let report = stack_vs_heap_report(&String::from("hello"));
+        assert_eq!(report.size, mem::size_of::<String>());
+        assert!(!report.copy_like);
+        assert!(report.heap_backed);
+        assert!(!report.stack_only);
+    }
+
+    #[test]
+    fn borrow_and_own_gives_one_shared_and_one_new_address() {
+        let original = String::from("hello");
+        let (borrowed, owned) = borrow_and_own(&original);
+
+        assert_eq!(borrowed.as_ptr(), original.as_ptr());
+        assert_ne!(owned.as_ptr(), original.as_ptr());
+        assert!(same_allocation(borrowed, &original));
+        assert!(!same_allocation(&owned, &original));
+        assert_eq!(owned, original);
+    }
+
+    #[test]
+    fn same_allocation_of_sub_slices() {
+        let s = "hello world";
+        assert!(same_allocation(&s[..5], &s[3..]));
+        assert!(!same_allocation(&s[..5], &s[5..]));
+        assert!(!same_allocation(&s[..0], s));
+    }
+
+    #[test]
+    fn small_str_keeps_short_text_inline() {
+        let s = SmallStr::new("hello");
+        assert!(s.is_inline());
+        assert_eq!(s.as_str(), "hello");
+
+        let full = "x".repeat(INLINE_CAP);
+        let s = SmallStr::from(full.as_str());
+        assert!(s.is_inline());
+        assert_eq!(s.as_str(), full);
+    }
+
+    #[test]
+    fn small_str_spills_long_text_to_the_heap() {
+        let long = "é".repeat(12); // 24 bytes, one more than fits
+        let s = SmallStr::new(&long);
+        assert!(!s.is_inline());
+        assert_eq!(s.as_str(), long);
+    }
+
+    #[test]
+    fn box_and_unbox_keeps_the_string_heap_block() {
+        let s = String::from("boxed");
+        let heap = s.as_ptr();
+        let back = box_and_unbox(s);
+        assert_eq!(back, "boxed");
+        // only the pointer, length and capacity moved; the text stayed put
+        assert_eq!(back.as_ptr(), heap);
+    }
+
+    #[test]
+    fn clone_breakdown_of_a_string_with_spare_capacity() {
+        let mut s = String::with_capacity(32);
+        s.push_str("hello");
+
+        let breakdown = clone_breakdown(&s);
+        assert_eq!(breakdown.stack_bytes, mem::size_of::<String>());
+        assert_eq!(breakdown.heap_bytes, s.capacity());
+        assert!(breakdown.heap_bytes >= 32);
+    }
+
+    #[test]
+    fn clone_breakdown_of_an_empty_string_has_no_heap_bytes() {
+        let breakdown = clone_breakdown(&String::new());
+        assert_eq!(breakdown.stack_bytes, mem::size_of::<String>());
+        assert_eq!(breakdown.heap_bytes, 0);
+    }
+
+    // describes itself as the names the log has seen dropped so far
+    struct Peek(crate::drops::DropLog);
+
+    impl Describable for Peek {
+        fn describe(&self) -> String {
+            format!("dropped so far: {:?}", self.0.borrow())
+        }
+    }
+
+    #[test]
+    fn describe_all_describes_each_item_then_drops_it() {
+        let log = crate::drops::DropLog::default();
+        let items: Vec<Box<dyn Describable>> = vec![
+            Box::new(String::from("héllo")),
+            Box::new(Loud::new("a", &log)),
+            Box::new(Peek(log.clone())),
+            Box::new(7i32),
+            Box::new(Loud::new("b", &log)),
+        ];
+
+        let descriptions = describe_all(items);
+        assert_eq!(
+            descriptions,
+            [
+                "a String of 6 bytes",
+                "a Loud named a",
+                r#"dropped so far: ["a"]"#,
+                "the number 7",
+                "a Loud named b",
+            ]
+        );
+        assert_eq!(*log.borrow(), ["a", "b"]);
+        assert!(describe_all(Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+/*
+
+Streaming
+
+Reading a whole file into a String before working on it means holding all of it in
+memory at once. Working through a reader a chunk at a time, with one buffer that is
+reused for every chunk, keeps memory use the same whether the input is ten bytes or
+ten gigabytes.
+
+*/
+
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+
+/// Copies `reader` to `writer`, uppercasing ASCII letters along the way, and returns
+/// how many bytes were copied.
+///
+/// Bytes outside ASCII are passed through untouched, which also keeps multibyte
+/// UTF-8 chars intact even when a chunk boundary splits one.
+pub fn uppercase_stream<R: Read, W: Write>(mut reader: R, mut writer: W) -> io::Result<u64> {
+    let mut buf = [0u8; 4096];
+    let mut total = 0;
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        buf[..n].make_ascii_uppercase();
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Reads `reader` one line at a time, yielding each line as an owned String
+/// without it's "\n" or "\r\n".
+///
+/// Every line is read into the same internal buffer, which keeps it's capacity
+/// from one line to the next; each yielded String is an exact-size copy. The last
+/// line is yielded even without a trailing newline. After an I/O error is
+/// yielded, the iterator ends.
+pub fn line_stream<R: BufRead>(mut reader: R) -> impl Iterator<Item = io::Result<String>> {
+    let mut buf = String::new();
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        buf.clear();
+        match reader.read_line(&mut buf) {
+            Ok(0) => {
+                done = true;
+                None
+            }
+            Ok(_) => {
+                let line = buf.strip_suffix('\n').unwrap_or(&buf);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                Some(Ok(line.to_string()))
+            }
+            Err(e) => {
+                done = true;
+                Some(Err(e))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn uppercase_stream_over_a_cursor() {
+        let mut out = Vec::new();
+        let n = uppercase_stream(Cursor::new("héllo, world"), &mut out).unwrap();
+        assert_eq!(n, "héllo, world".len() as u64);
+        assert_eq!(out, "HéLLO, WORLD".as_bytes());
+    }
+
+    #[test]
+    fn uppercase_stream_across_several_refills() {
+        // longer than the 4096-byte buffer, with 'é' split over the first boundary
+        let input = format!("{}é{}", "a".repeat(4095), "b".repeat(5000));
+        let mut out = Vec::new();
+        let n = uppercase_stream(Cursor::new(input.as_bytes()), &mut out).unwrap();
+        assert_eq!(n, input.len() as u64);
+        assert_eq!(
+            out,
+            format!("{}é{}", "A".repeat(4095), "B".repeat(5000)).as_bytes()
+        );
+    }
+
+    #[test]
+    fn line_stream_strips_both_kinds_of_line_ending() {
+        let lines: Vec<String> = line_stream(Cursor::new("one\r\ntwo\n\nthree\n"))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, ["one", "two", "", "three"]);
+    }
+
+    #[test]
+    fn line_stream_yields_a_last_line_without_a_newline() {
+        let lines: Vec<String> = line_stream(Cursor::new("a\nb"))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, ["a", "b"]);
+        assert_eq!(line_stream(Cursor::new("")).count(), 0);
+    }
+
+    #[test]
+    fn line_stream_ends_after_an_error() {
+        // read_line fails on bytes that aren't UTF-8
+        let mut lines = line_stream(Cursor::new(b"ok\n\xFF\nnever\n"));
+        assert_eq!(lines.next().unwrap().unwrap(), "ok");
+        let err = lines.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(lines.next().is_none());
+    }
+}
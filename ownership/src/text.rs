@@ -0,0 +1,626 @@
+/*
+
+Text transforms
+
+These helpers borrow the input as a &str and give back a brand new String. The
+caller keeps ownership of what they passed in, and becomes the owner of the result.
+
+Indexing a String by bytes can land in the middle of a multibyte character, so
+everything here walks the text one char at a time instead.
+
+*/
+
+use std::borrow::Cow;
+
+/// Replaces all but the last `reveal_last` chars of `s` with `mask`.
+///
+/// Masking is done per char, so a multibyte char becomes exactly one `mask` and
+/// a codepoint is never split. If `reveal_last` covers the whole string, an
+/// unmasked copy of the input is returned.
+pub fn redact(s: &str, reveal_last: usize, mask: char) -> String {
+    let char_count = s.chars().count();
+    if reveal_last >= char_count {
+        return s.to_string();
+    }
+
+    let hidden = char_count - reveal_last;
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate() {
+        out.push(if i < hidden { mask } else { c });
+    }
+    out
+}
+
+/*
+
+Owned or borrowed input
+
+Some functions need an owned String to work on. If the caller already has one they
+no longer need, it can be moved in for free; if they only have a &str, a copy has
+to be made. A trait lets one function accept both and only clone when it must.
+
+*/
+
+/// Turns text into an owned String, cloning only if it was borrowed.
+pub trait IntoTextOwned {
+    fn into_text_owned(self) -> String;
+}
+
+impl IntoTextOwned for &str {
+    fn into_text_owned(self) -> String {
+        self.to_string() // we only borrowed it, so we need our own copy
+    }
+}
+
+impl IntoTextOwned for String {
+    fn into_text_owned(self) -> String {
+        self // already owned, just move it through
+    }
+}
+
+/// Lowercases the ASCII letters of `t` in place.
+///
+/// Passing a String reuses it's buffer; passing a &str allocates one copy.
+pub fn normalize<T: IntoTextOwned>(t: T) -> String {
+    let mut s = t.into_text_owned();
+    s.make_ascii_lowercase();
+    s
+}
+
+/// Counts the single-char insertions, deletions and substitutions needed to turn
+/// `a` into `b`.
+///
+/// Chars are compared, not bytes, so "é" vs "e" is one edit rather than two. Only
+/// the shorter input's chars and two rows of the distance table are stored, so the
+/// memory used grows with the shorter input alone; the longer one is only walked.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let (long, short) = if a.chars().count() >= b.chars().count() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let short: Vec<char> = short.chars().collect();
+
+    // prev[j] is the distance between the first i chars of long and first j of short
+    let mut prev: Vec<usize> = (0..=short.len()).collect();
+    let mut curr = vec![0; short.len() + 1];
+
+    for (i, lc) in long.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, &sc) in short.iter().enumerate() {
+            let substitution = prev[j] + usize::from(lc != sc);
+            let deletion = prev[j + 1] + 1;
+            let insertion = curr[j] + 1;
+            curr[j + 1] = substitution.min(deletion).min(insertion);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[short.len()]
+}
+
+/// Writes `parts` separated by `sep` straight into `w`.
+///
+/// `parts.join(sep)` would build a new String just to hold the result. When the
+/// caller already has somewhere to put the text, writing into it directly skips
+/// that allocation.
+pub fn write_joined<W: std::fmt::Write>(w: &mut W, parts: &[&str], sep: &str) -> std::fmt::Result {
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            w.write_str(sep)?;
+        }
+        w.write_str(part)?;
+    }
+    Ok(())
+}
+
+/// Replaces every `from` in `s` with `to`, allocating only if there is a match.
+///
+/// `s.replace(from, to)` always builds a new String, even when nothing changes.
+/// Here the input is borrowed back untouched when `from` doesn't occur. Matches
+/// are found left to right without overlapping, and an empty `from` matches
+/// between every char, just like `str::replace`.
+pub fn replace_cow<'a>(s: &'a str, from: &str, to: &str) -> Cow<'a, str> {
+    if s.contains(from) {
+        Cow::Owned(s.replace(from, to))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Collapses each run of the same char into one: "aaabbb" becomes "ab".
+pub fn dedup_chars(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut prev = None;
+    for c in s.chars() {
+        if prev != Some(c) {
+            out.push(c);
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+/// Does what [`dedup_chars`] does, but in the caller's String, so it's buffer and
+/// capacity are reused instead of building a new one.
+pub fn dedup_chars_in_place(buf: &mut String) {
+    let mut prev = None;
+    buf.retain(|c| {
+        let keep = prev != Some(c);
+        prev = Some(c);
+        keep
+    });
+}
+
+/// Returns the lines of `text` in reverse order, each one left as it was.
+///
+/// Lines are joined with "\r\n" if the text's first line break is "\r\n", and
+/// with "\n" otherwise. A trailing newline stays at the end of the output rather
+/// than turning into an empty first line.
+pub fn reverse_lines(text: &str) -> String {
+    let newline = match text.find('\n') {
+        Some(i) if text[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.lines().rev().enumerate() {
+        if i > 0 {
+            out.push_str(newline);
+        }
+        out.push_str(line);
+    }
+    if text.ends_with('\n') {
+        out.push_str(newline);
+    }
+    out
+}
+
+/// Moves the first `n` chars of `s` to the end: "abcde" rotated by 2 is "cdeab".
+///
+/// `n` wraps around, so rotating by the char count (or a multiple of it) gives
+/// back a copy of `s`.
+pub fn rotate_left(s: &str, n: usize) -> String {
+    let char_count = s.chars().count();
+    if char_count == 0 {
+        return String::new();
+    }
+
+    // split at the byte offset where char number n % char_count starts
+    let split = s.char_indices().nth(n % char_count).map_or(0, |(i, _)| i);
+    let (head, tail) = s.split_at(split);
+
+    let mut out = String::with_capacity(s.len());
+    out.push_str(tail);
+    out.push_str(head);
+    out
+}
+
+/// How two strings differ: the ends they share, and owned copies of the middles
+/// that differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSummary {
+    /// Bytes at the start that both strings share.
+    pub common_prefix_len: usize,
+    /// Bytes at the end that both strings share, not counting the prefix.
+    pub common_suffix_len: usize,
+    pub a_middle: String,
+    pub b_middle: String,
+}
+
+/// Compares `a` and `b` char by char from both ends.
+///
+/// The shared ends are only reported as byte lengths, so nothing is copied for
+/// them; the differing middles are copied, so the summary doesn't borrow `a` or
+/// `b`. The prefix and suffix never overlap.
+pub fn diff_summary(a: &str, b: &str) -> DiffSummary {
+    let prefix: usize = a
+        .chars()
+        .zip(b.chars())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x.len_utf8())
+        .sum();
+
+    let (a_rest, b_rest) = (&a[prefix..], &b[prefix..]);
+    let suffix: usize = a_rest
+        .chars()
+        .rev()
+        .zip(b_rest.chars().rev())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x.len_utf8())
+        .sum();
+
+    DiffSummary {
+        common_prefix_len: prefix,
+        common_suffix_len: suffix,
+        a_middle: a_rest[..a_rest.len() - suffix].to_string(),
+        b_middle: b_rest[..b_rest.len() - suffix].to_string(),
+    }
+}
+
+/// Replaces each tab in `s` with enough spaces to reach the next tab stop.
+///
+/// Tab stops sit every `tab_width` columns, a column being one char, and the
+/// column count starts over after each newline. A `tab_width` of 0 leaves no
+/// room for a stop, so tabs are simply dropped.
+pub fn expand_tabs(s: &str, tab_width: usize) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut column = 0;
+    for c in s.chars() {
+        match c {
+            '\t' if tab_width > 0 => {
+                let spaces = tab_width - column % tab_width;
+                out.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\t' => {}
+            '\n' => {
+                out.push(c);
+                column = 0;
+            }
+            _ => {
+                out.push(c);
+                column += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Reverses the order of the words in `s`, keeping each word as it is: "the quick
+/// fox" becomes "fox quick the".
+///
+/// Words are separated by whitespace. Runs of it come out as one space, and
+/// whitespace at either end is dropped.
+pub fn reverse_words(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (i, word) in s.split_whitespace().rev().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+/// Returns `word` in lowercase, uppercase and title case, in that order, with
+/// repeats left out.
+///
+/// Storing these owned forms up front lets a lookup try each one without
+/// lowercasing anything at lookup time. Only ASCII letters change case; other
+/// chars, "é" included, are kept as they are, so non-ASCII words get best-effort
+/// variants. A word such as "1" that has no letters gives just one variant.
+pub fn case_variants(word: &str) -> Vec<String> {
+    let lower = word.to_ascii_lowercase();
+    let upper = word.to_ascii_uppercase();
+    let mut title = lower.clone();
+    if let Some(first) = title.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+
+    let mut variants = Vec::with_capacity(3);
+    for v in [lower, upper, title] {
+        if !variants.contains(&v) {
+            variants.push(v);
+        }
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_keeps_the_last_chars() {
+        assert_eq!(redact("secret1234", 4, '*'), "******1234");
+    }
+
+    #[test]
+    fn redact_revealing_everything_copies_the_input() {
+        assert_eq!(redact("abc", 3, '*'), "abc");
+        assert_eq!(redact("abc", 10, '*'), "abc");
+    }
+
+    #[test]
+    fn redact_revealing_nothing_masks_every_char() {
+        assert_eq!(redact("abc", 0, '#'), "###");
+    }
+
+    #[test]
+    fn redact_masks_multibyte_chars_one_for_one() {
+        assert_eq!(redact("héllo wörld", 3, '*'), "********rld");
+        assert_eq!(redact("日本語", 1, '•'), "••語");
+    }
+
+    #[test]
+    fn normalize_lowercases_ascii() {
+        assert_eq!(normalize("HeLLo ÉTÉ"), "hello ÉtÉ");
+        assert_eq!(normalize(String::from("ABC")), "abc");
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn normalize_only_copies_borrowed_input() {
+        use crate::alloc_count::count_allocations;
+
+        let owned = String::from("HELLO");
+        let (_, n) = count_allocations(|| normalize(owned));
+        assert_eq!(n, 0);
+
+        let (_, n) = count_allocations(|| normalize("HELLO"));
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn levenshtein_of_identical_strings_is_zero() {
+        assert_eq!(levenshtein("kitten", "kitten"), 0);
+        assert_eq!(levenshtein("", ""), 0);
+    }
+
+    #[test]
+    fn levenshtein_kitten_sitting() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("sitting", "kitten"), 3);
+    }
+
+    #[test]
+    fn levenshtein_against_empty_is_the_char_count() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("héé", ""), 3);
+    }
+
+    #[test]
+    fn levenshtein_counts_multibyte_chars_once() {
+        assert_eq!(levenshtein("café", "cafe"), 1);
+        assert_eq!(levenshtein("日本", "日本語"), 1);
+    }
+
+    #[test]
+    fn write_joined_into_a_string() {
+        let mut out = String::from("> ");
+        write_joined(&mut out, &["a", "b", "c"], ", ").unwrap();
+        assert_eq!(out, "> a, b, c");
+
+        let mut out = String::new();
+        write_joined(&mut out, &[], ", ").unwrap();
+        assert_eq!(out, "");
+    }
+
+    // a sink that keeps nothing, only counts what it's given
+    #[derive(Default)]
+    struct CountingWriter {
+        writes: usize,
+        bytes: usize,
+    }
+
+    impl std::fmt::Write for CountingWriter {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.writes += 1;
+            self.bytes += s.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_joined_into_a_counting_writer() {
+        let mut w = CountingWriter::default();
+        write_joined(&mut w, &["ab", "cd", "é"], "-").unwrap();
+        assert_eq!(w.writes, 5);
+        assert_eq!(w.bytes, "ab-cd-é".len());
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn write_joined_builds_no_intermediate_string() {
+        use crate::alloc_count::count_allocations;
+
+        let mut out = String::with_capacity(64);
+        let (_, n) = count_allocations(|| write_joined(&mut out, &["a", "b", "c"], ", "));
+        assert_eq!(n, 0);
+
+        let mut w = CountingWriter::default();
+        let (_, n) = count_allocations(|| write_joined(&mut w, &["a", "b", "c"], ", "));
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn replace_cow_without_a_match_borrows() {
+        let s = "hello world";
+        let out = replace_cow(s, "xyz", "abc");
+        assert!(matches!(out, Cow::Borrowed(b) if b.as_ptr() == s.as_ptr()));
+    }
+
+    #[test]
+    fn replace_cow_with_a_match_owns() {
+        let out = replace_cow("hello world", "o", "0");
+        assert!(matches!(&out, Cow::Owned(o) if o == "hell0 w0rld"));
+    }
+
+    #[test]
+    fn replace_cow_does_not_overlap_matches() {
+        assert_eq!(replace_cow("aaa", "aa", "b"), "ba");
+    }
+
+    #[test]
+    fn replace_cow_with_empty_from_matches_between_chars() {
+        let out = replace_cow("hé", "", "-");
+        assert!(matches!(out, Cow::Owned(_)));
+        assert_eq!(out, "-h-é-");
+    }
+
+    #[test]
+    fn dedup_chars_of_empty_and_unique_text() {
+        assert_eq!(dedup_chars(""), "");
+        assert_eq!(dedup_chars("abcab"), "abcab");
+    }
+
+    #[test]
+    fn dedup_chars_collapses_runs() {
+        assert_eq!(dedup_chars("aaaa"), "a");
+        assert_eq!(dedup_chars("aaabbbcca"), "abca");
+        assert_eq!(dedup_chars("ééé🦀🦀x"), "é🦀x");
+    }
+
+    #[test]
+    fn dedup_chars_in_place_keeps_the_buffer() {
+        let mut buf = String::with_capacity(64);
+        buf.push_str("hhéééllo");
+        let (heap, capacity) = (buf.as_ptr(), buf.capacity());
+
+        dedup_chars_in_place(&mut buf);
+        assert_eq!(buf, "hélo");
+        assert_eq!(buf.as_ptr(), heap);
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn reverse_lines_of_three_lines() {
+        assert_eq!(reverse_lines("one\ntwo\nthree"), "three\ntwo\none");
+    }
+
+    #[test]
+    fn reverse_lines_of_one_line() {
+        assert_eq!(reverse_lines("only"), "only");
+        assert_eq!(reverse_lines(""), "");
+    }
+
+    #[test]
+    fn reverse_lines_keeps_a_trailing_newline_at_the_end() {
+        assert_eq!(reverse_lines("a\nb\n"), "b\na\n");
+    }
+
+    #[test]
+    fn reverse_lines_keeps_crlf() {
+        assert_eq!(reverse_lines("a\r\nb\r\nc\r\n"), "c\r\nb\r\na\r\n");
+    }
+
+    #[test]
+    fn rotate_left_by_zero_or_the_length_is_a_copy() {
+        assert_eq!(rotate_left("abcde", 0), "abcde");
+        assert_eq!(rotate_left("abcde", 5), "abcde");
+        assert_eq!(rotate_left("", 3), "");
+    }
+
+    #[test]
+    fn rotate_left_counts_chars_not_bytes() {
+        assert_eq!(rotate_left("abcde", 2), "cdeab");
+        assert_eq!(rotate_left("héllo", 2), "llohé");
+    }
+
+    #[test]
+    fn rotate_left_wraps_around() {
+        assert_eq!(rotate_left("abcde", 7), "cdeab");
+        assert_eq!(rotate_left("日本語", 4), "本語日");
+    }
+
+    fn diff(prefix: usize, suffix: usize, a: &str, b: &str) -> DiffSummary {
+        DiffSummary {
+            common_prefix_len: prefix,
+            common_suffix_len: suffix,
+            a_middle: a.to_string(),
+            b_middle: b.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_summary_of_identical_strings() {
+        assert_eq!(diff_summary("same", "same"), diff(4, 0, "", ""));
+        assert_eq!(diff_summary("", ""), diff(0, 0, "", ""));
+    }
+
+    #[test]
+    fn diff_summary_of_completely_different_strings() {
+        assert_eq!(diff_summary("abc", "xyz"), diff(0, 0, "abc", "xyz"));
+    }
+
+    #[test]
+    fn diff_summary_with_only_a_shared_prefix_or_suffix() {
+        assert_eq!(diff_summary("héllo", "hélp"), diff(4, 0, "lo", "p"));
+        assert_eq!(diff_summary("cat", "hat"), diff(0, 2, "c", "h"));
+        assert_eq!(diff_summary("a_b", "a__b"), diff(2, 1, "", "_"));
+    }
+
+    #[test]
+    fn expand_tabs_at_the_start_and_in_the_middle() {
+        assert_eq!(expand_tabs("\tx", 4), "    x");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        // columns are chars, so 'é' takes one
+        assert_eq!(expand_tabs("é\tz", 4), "é   z");
+    }
+
+    #[test]
+    fn expand_tabs_several_in_a_row_and_over_lines() {
+        assert_eq!(expand_tabs("a\t\tb", 4), "a       b");
+        assert_eq!(expand_tabs("abc\td\n\te", 4), "abc d\n    e");
+    }
+
+    #[test]
+    fn expand_tabs_with_widths_one_eight_and_zero() {
+        assert_eq!(expand_tabs("a\tb\t", 1), "a b ");
+        assert_eq!(expand_tabs("a\tb", 8), "a       b");
+        assert_eq!(expand_tabs("a\tb", 0), "ab");
+    }
+
+    #[test]
+    fn reverse_words_reverses_word_order() {
+        assert_eq!(reverse_words("the quick fox"), "fox quick the");
+    }
+
+    #[test]
+    fn reverse_words_squeezes_whitespace() {
+        assert_eq!(reverse_words("  a \t b\n\nc  "), "c b a");
+    }
+
+    #[test]
+    fn reverse_words_keeps_each_word_intact() {
+        assert_eq!(reverse_words("héllo wörld"), "wörld héllo");
+        assert_eq!(reverse_words("alone"), "alone");
+    }
+
+    #[test]
+    fn reverse_words_of_nothing() {
+        assert_eq!(reverse_words(""), "");
+        assert_eq!(reverse_words("   "), "");
+    }
+
+    #[test]
+    fn case_variants_of_a_lowercase_word() {
+        assert_eq!(case_variants("abc"), ["abc", "ABC", "Abc"]);
+    }
+
+    #[test]
+    fn case_variants_of_an_uppercase_word() {
+        assert_eq!(case_variants("ABC"), ["abc", "ABC", "Abc"]);
+        assert_eq!(case_variants("A"), ["a", "A"]);
+    }
+
+    #[test]
+    fn case_variants_leave_out_repeats() {
+        assert_eq!(case_variants(""), [""]);
+        assert_eq!(case_variants("1"), ["1"]);
+        assert_eq!(case_variants("é"), ["é"]);
+        assert_eq!(case_variants("éa"), ["éa", "éA"]);
+    }
+
+    #[test]
+    fn levenshtein_picks_the_shorter_input_by_chars() {
+        // "ééé" has more bytes than "abcd" but fewer chars
+        assert_eq!(levenshtein("ééé", "abcd"), 4);
+        assert_eq!(levenshtein("abcd", "ééé"), 4);
+        assert_eq!(levenshtein("aéb", "aébcd"), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "count-allocations")]
+    fn levenshtein_memory_does_not_grow_with_the_longer_input() {
+        use crate::alloc_count::count_allocations;
+
+        let long = "x".repeat(10_000);
+        let (d, n) = count_allocations(|| levenshtein("ab", &long));
+        assert_eq!(d, 10_000);
+        // the short input's chars, and the two rows
+        assert_eq!(n, 3);
+    }
+}
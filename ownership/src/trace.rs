@@ -0,0 +1,371 @@
+/*
+
+Tracing ownership
+
+The examples in main.rs describe what happens to a value in comments: it comes into
+scope, it's moved to another variable, it's dropped. Writing those steps down as
+data lets us build the same story in code, check it, and print it.
+
+    let s1 = String::from("hello"); // Declared("s1")
+    let s2 = s1;                    // Moved { from: "s1", to: "s2" }
+                                    // Dropped("s2"), s1 was moved so nothing happens for it
+
+*/
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Declared(String),
+    Moved { from: String, to: String },
+    Dropped(String),
+}
+
+/// Builds up a list of [`Event`]s one step at a time.
+#[derive(Debug, Clone, Default)]
+pub struct TraceBuilder {
+    events: Vec<Event>,
+}
+
+impl TraceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn declare(mut self, name: &str) -> Self {
+        self.events.push(Event::Declared(name.to_string()));
+        self
+    }
+
+    pub fn moved(mut self, from: &str, to: &str) -> Self {
+        self.events.push(Event::Moved {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    pub fn dropped(mut self, name: &str) -> Self {
+        self.events.push(Event::Dropped(name.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Vec<Event> {
+        self.events
+    }
+}
+
+/// Renders `events` as a numbered timeline, one step per line.
+pub fn replay(events: &[Event]) -> String {
+    let mut out = String::new();
+    for (i, event) in events.iter().enumerate() {
+        // writing to a String can't fail
+        let _ = match event {
+            Event::Declared(name) => writeln!(out, "{}. {name} comes into scope", i + 1),
+            Event::Moved { from, to } => writeln!(out, "{}. {from} is moved into {to}", i + 1),
+            Event::Dropped(name) => writeln!(out, "{}. {name} is dropped", i + 1),
+        };
+    }
+    out
+}
+
+/*
+
+A tiny move checker
+
+The compiler rejects a program that uses a variable after it's value was moved out.
+analyze_moves does the same for a toy language with just two statements:
+
+    let x = y;   binds x. If y is a binding, it's value moves into x and y can't
+                 be used anymore; otherwise y is taken to make a fresh value
+    use x;       reads x, which is an error if x was moved from
+
+So "let a = mk; let b = a; use a;" is rejected at it's third statement, the same
+way the compiler rejects using s1 after let s2 = s1;.
+
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    /// A binding was used after it's value moved out. `statement` counts from 1.
+    UseAfterMove { name: String, statement: usize },
+    /// A binding was used without ever being declared.
+    NotDeclared { name: String, statement: usize },
+    /// A statement wasn't `let x = y` or `use x`.
+    Syntax { text: String, statement: usize },
+}
+
+/// What [`analyze_moves`] found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveReport {
+    /// Bindings that were moved from and are still invalid at the end, in the
+    /// order they were moved.
+    pub moved_from: Vec<String>,
+    pub errors: Vec<MoveError>,
+}
+
+impl MoveReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Checks a program in the toy language above for uses of moved-from bindings.
+pub fn analyze_moves(src: &str) -> MoveReport {
+    let mut report = MoveReport::default();
+    // binding -> whether it's value has been moved out
+    let mut moved: HashMap<&str, bool> = HashMap::new();
+
+    let statements = src.split(';').map(str::trim).filter(|s| !s.is_empty());
+    for (i, stmt) in statements.enumerate() {
+        let statement = i + 1;
+
+        if let Some(rest) = stmt.strip_prefix("let ") {
+            let Some((name, init)) = rest.split_once('=') else {
+                report.errors.push(MoveError::Syntax {
+                    text: stmt.to_string(),
+                    statement,
+                });
+                continue;
+            };
+            let (name, init) = (name.trim(), init.trim());
+            if !is_identifier(name) || !is_identifier(init) {
+                report.errors.push(MoveError::Syntax {
+                    text: stmt.to_string(),
+                    statement,
+                });
+                continue;
+            }
+
+            // moving out of a binding is a use of it
+            match moved.get(init) {
+                Some(true) => report.errors.push(MoveError::UseAfterMove {
+                    name: init.to_string(),
+                    statement,
+                }),
+                Some(false) => {
+                    moved.insert(init, true);
+                    report.moved_from.push(init.to_string());
+                }
+                None => {} // not a binding, so this makes a fresh value
+            }
+
+            // declaring a name again gives it a new, valid value
+            moved.insert(name, false);
+            report.moved_from.retain(|m| m != name);
+        } else if let Some(name) = stmt.strip_prefix("use ") {
+            let name = name.trim();
+            match moved.get(name) {
+                Some(true) => report.errors.push(MoveError::UseAfterMove {
+                    name: name.to_string(),
+                    statement,
+                }),
+                Some(false) => {}
+                None => report.errors.push(MoveError::NotDeclared {
+                    name: name.to_string(),
+                    statement,
+                }),
+            }
+        } else {
+            report.errors.push(MoveError::Syntax {
+                text: stmt.to_string(),
+                statement,
+            });
+        }
+    }
+
+    report
+}
+
+/*
+
+Drawing lifetimes
+
+render_lifetime_chart turns a list of events into one row per binding, with one
+column per event, so we can see at a glance who is alive when. Declaring s, borrowing
+r from it, declaring t, then dropping r, t and s in that order draws:
+
+    s |O====x
+    r | &-x
+    t |  O=x
+
+O marks a binding coming into scope and = that it is alive; & marks a borrow
+starting and - that it is live; x marks a drop, or the end of a borrow.
+
+A borrow's row has to end before the row of the value it borrows from; that is
+the rule the compiler checks when it says a value "does not live long enough".
+
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifetimeEvent {
+    /// An owning binding comes into scope.
+    Declared(String),
+    /// `name` starts borrowing from the binding `from`.
+    Borrowed { name: String, from: String },
+    /// A binding is dropped, or a borrow stops being used.
+    Dropped(String),
+}
+
+// one row of the chart: where it starts and ends, and whether it's a borrow
+struct Row<'a> {
+    name: &'a str,
+    start: usize,
+    end: Option<usize>,
+    borrow: bool,
+}
+
+/// Draws `events` as an ASCII chart, one row per binding in the order they first
+/// appear. A binding that is never dropped runs to the last column.
+pub fn render_lifetime_chart(events: &[LifetimeEvent]) -> String {
+    let mut rows: Vec<Row> = Vec::new();
+    for (step, event) in events.iter().enumerate() {
+        match event {
+            LifetimeEvent::Declared(name) => rows.push(Row {
+                name,
+                start: step,
+                end: None,
+                borrow: false,
+            }),
+            LifetimeEvent::Borrowed { name, .. } => rows.push(Row {
+                name,
+                start: step,
+                end: None,
+                borrow: true,
+            }),
+            LifetimeEvent::Dropped(name) => {
+                // the most recent row for that name that's still open
+                if let Some(row) = rows
+                    .iter_mut()
+                    .rev()
+                    .find(|r| r.name == name && r.end.is_none())
+                {
+                    row.end = Some(step);
+                }
+            }
+        }
+    }
+
+    // format! pads by chars, so the width has to be counted in chars too
+    let width = rows
+        .iter()
+        .map(|r| r.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let last = events.len().saturating_sub(1);
+    let mut out = String::new();
+
+    for row in &rows {
+        let (open, fill) = if row.borrow { ('&', '-') } else { ('O', '=') };
+        let end = row.end.unwrap_or(last);
+
+        let mut line = format!("{:width$} |", row.name);
+        for step in 0..=end {
+            line.push(match step {
+                s if s < row.start => ' ',
+                s if s == row.start => open,
+                s if Some(s) == row.end => 'x',
+                _ => fill,
+            });
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_of_let_s2_equals_s1() {
+        let events = TraceBuilder::new()
+            .declare("s1")
+            .moved("s1", "s2")
+            .dropped("s2")
+            .build();
+
+        assert_eq!(
+            events,
+            [
+                Event::Declared("s1".to_string()),
+                Event::Moved {
+                    from: "s1".to_string(),
+                    to: "s2".to_string()
+                },
+                Event::Dropped("s2".to_string()),
+            ]
+        );
+        assert_eq!(
+            replay(&events),
+            "1. s1 comes into scope\n2. s1 is moved into s2\n3. s2 is dropped\n"
+        );
+    }
+
+    #[test]
+    fn analyze_moves_rejects_use_after_move() {
+        let report = analyze_moves("let a = mk; let b = a; use a;");
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.errors,
+            [MoveError::UseAfterMove {
+                name: "a".to_string(),
+                statement: 3
+            }]
+        );
+        assert_eq!(report.moved_from, ["a"]);
+    }
+
+    #[test]
+    fn analyze_moves_accepts_using_the_new_owner() {
+        let report = analyze_moves("let a = mk; let b = a; use b;");
+        assert!(report.is_ok());
+        assert_eq!(report.moved_from, ["a"]);
+    }
+
+    fn borrow_chart(owner: &str, borrow: &str) -> String {
+        render_lifetime_chart(&[
+            LifetimeEvent::Declared(owner.to_string()),
+            LifetimeEvent::Borrowed {
+                name: borrow.to_string(),
+                from: owner.to_string(),
+            },
+            LifetimeEvent::Dropped(borrow.to_string()),
+            LifetimeEvent::Dropped(owner.to_string()),
+        ])
+    }
+
+    #[test]
+    fn lifetime_chart_ends_the_borrow_before_its_owner() {
+        let chart = borrow_chart("s", "r");
+        assert_eq!(chart, "s |O==x\nr | &x\n");
+
+        let ends: Vec<usize> = chart.lines().map(|l| l.rfind('x').unwrap()).collect();
+        assert!(ends[1] < ends[0]);
+    }
+
+    #[test]
+    fn lifetime_chart_pads_multibyte_names_by_char() {
+        assert_eq!(borrow_chart("ré", "r"), "ré |O==x\nr  | &x\n");
+    }
+
+    #[test]
+    fn lifetime_chart_runs_an_undropped_binding_to_the_end() {
+        let chart = render_lifetime_chart(&[
+            LifetimeEvent::Declared("a".to_string()),
+            LifetimeEvent::Declared("b".to_string()),
+            LifetimeEvent::Dropped("b".to_string()),
+        ]);
+        assert_eq!(chart, "a |O==\nb | Ox\n");
+        assert_eq!(render_lifetime_chart(&[]), "");
+    }
+}
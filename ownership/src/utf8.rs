@@ -0,0 +1,401 @@
+/*
+
+UTF-8
+
+A String is always valid UTF-8, and a char can take up to four bytes. When text
+arrives as raw bytes, in pieces, a multibyte char can be cut in half at the end of
+one piece and finished at the start of the next. Each piece on its own may not be
+valid UTF-8 even though the whole thing is.
+
+*/
+
+use std::ops::Range;
+
+use crate::error::TextError;
+
+/// Assembles an owned String out of byte chunks, checking UTF-8 as it goes.
+///
+/// An incomplete multibyte sequence at the end of a chunk is held back until the
+/// next chunk arrives, so chunks don't have to be valid on their own.
+#[derive(Debug, Default)]
+pub struct Utf8Builder {
+    text: String,
+    pending: Vec<u8>,
+}
+
+impl Utf8Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a chunk of bytes.
+    ///
+    /// Returns [`TextError::InvalidUtf8`] as soon as the bytes can't be valid
+    /// UTF-8 no matter what comes next. The offsets in the wrapped error are
+    /// relative to the held-back bytes plus this chunk. The text before the bad
+    /// sequence is kept, but the rest of the chunk is dropped along with any
+    /// held-back bytes, so the next chunk starts fresh instead of failing too.
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> Result<(), TextError> {
+        self.pending.extend_from_slice(chunk);
+
+        let (valid_up_to, error) = match std::str::from_utf8(&self.pending) {
+            Ok(s) => {
+                self.text.push_str(s);
+                self.pending.clear();
+                return Ok(());
+            }
+            // error_len() is None when the input just ends too early
+            Err(e) if e.error_len().is_none() => (e.valid_up_to(), None),
+            Err(e) => (e.valid_up_to(), Some(e)),
+        };
+
+        // the prefix was just checked, so this can't fail
+        let valid = std::str::from_utf8(&self.pending[..valid_up_to]).unwrap();
+        self.text.push_str(valid);
+        match error {
+            Some(e) => {
+                self.pending.clear();
+                Err(e.into())
+            }
+            None => {
+                self.pending.drain(..valid_up_to);
+                Ok(())
+            }
+        }
+    }
+
+    /// Hands over the assembled String, or an error if the input stopped in the
+    /// middle of a char.
+    pub fn finish(self) -> Result<String, TextError> {
+        std::str::from_utf8(&self.pending)?;
+        Ok(self.text)
+    }
+}
+
+/*
+
+Chars and bytes
+
+s.len() counts bytes, not chars, so the nth char of a String is not at byte n once
+anything but ASCII shows up. "é" is one char but two bytes, and slicing &s[0..1]
+would cut it in half and panic.
+
+*/
+
+/// Yields each char's index along with the byte range it occupies in `s`.
+///
+/// Every range can be used to slice `s` without panicking.
+pub fn char_byte_ranges(s: &str) -> impl Iterator<Item = (usize, Range<usize>)> + '_ {
+    s.char_indices()
+        .enumerate()
+        .map(|(i, (start, c))| (i, start..start + c.len_utf8()))
+}
+
+/// Returns the byte offset at which char number `n` (counting from 0) starts, or
+/// `None` if `s` has `n` chars or fewer.
+pub fn nth_char_offset(s: &str, n: usize) -> Option<usize> {
+    s.char_indices().nth(n).map(|(i, _)| i)
+}
+
+/// Iterator returned by [`char_windows`].
+#[derive(Debug, Clone)]
+pub struct CharWindows<'a> {
+    s: &'a str,
+    start: usize,
+    // end of the current window, or None once there are no windows left
+    end: Option<usize>,
+}
+
+/// Yields every run of `size` consecutive chars in `s` as a borrowed slice,
+/// moving forward one char at a time.
+///
+/// A `size` of 0, or one larger than the number of chars, yields nothing.
+pub fn char_windows(s: &str, size: usize) -> CharWindows<'_> {
+    let end = if size == 0 {
+        None
+    } else {
+        // the window ends where char number `size` would start
+        s.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(s.len()))
+            .nth(size)
+    };
+
+    CharWindows { s, start: 0, end }
+}
+
+impl<'a> Iterator for CharWindows<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let end = self.end?;
+        let window = &self.s[self.start..end];
+
+        // slide both edges forward by one char
+        self.start += window.chars().next().map_or(0, char::len_utf8);
+        self.end = self.s[end..].chars().next().map(|c| end + c.len_utf8());
+
+        Some(window)
+    }
+}
+
+/// Checks that `range` can be used to slice `s`: both ends within bounds, start
+/// not after end, and both ends on char boundaries.
+pub fn is_char_boundary_range(s: &str, range: Range<usize>) -> bool {
+    range.start <= range.end && s.is_char_boundary(range.start) && s.is_char_boundary(range.end)
+}
+
+/// Borrows `s[range]`, or returns [`TextError::OutOfBounds`] where indexing
+/// would panic.
+pub fn safe_slice(s: &str, range: Range<usize>) -> Result<&str, TextError> {
+    if !is_char_boundary_range(s, range.clone()) {
+        return Err(TextError::OutOfBounds);
+    }
+    Ok(&s[range])
+}
+
+/// Shortens `buf` to at most `max_bytes` bytes without splitting a char.
+///
+/// `String::truncate` panics if the cut would land inside a char; here the cut is
+/// moved back to the start of that char instead. Longer limits leave `buf` as it is.
+pub fn truncate_to_bytes(buf: &mut String, max_bytes: usize) {
+    if max_bytes >= buf.len() {
+        return;
+    }
+
+    let mut end = max_bytes;
+    while !buf.is_char_boundary(end) {
+        end -= 1;
+    }
+    buf.truncate(end);
+}
+
+/// How the bytes of some text split between ASCII and multibyte chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AsciiStats {
+    /// Bytes that are chars on their own, one byte each.
+    pub ascii_bytes: usize,
+    /// Chars that take two to four bytes.
+    pub multibyte_chars: usize,
+    /// The most bytes any one char takes, or 0 for empty text.
+    pub max_char_bytes: usize,
+}
+
+/// Counts the ASCII bytes and multibyte chars in `s`.
+///
+/// `s.len()` is always `ascii_bytes` plus the bytes of the multibyte chars, so
+/// text with `multibyte_chars` of 0 has as many chars as it has bytes.
+pub fn ascii_stats(s: &str) -> AsciiStats {
+    let mut stats = AsciiStats::default();
+    for c in s.chars() {
+        let width = c.len_utf8();
+        if width == 1 {
+            stats.ascii_bytes += 1;
+        } else {
+            stats.multibyte_chars += 1;
+        }
+        stats.max_char_bytes = stats.max_char_bytes.max(width);
+    }
+    stats
+}
+
+/// Borrows `s` without the byte order mark (U+FEFF) some editors put at the start
+/// of UTF-8 files.
+///
+/// In UTF-8 the mark is the three bytes EF BB BF. Only one leading mark is
+/// removed; text without one comes back as it is.
+pub fn strip_bom(s: &str) -> &str {
+    s.strip_prefix('\u{FEFF}').unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_builder_joins_a_char_split_across_pushes() {
+        let bytes = "né".as_bytes(); // 'é' is the two bytes C3 A9
+        let mut builder = Utf8Builder::new();
+        builder.push_bytes(&bytes[..2]).unwrap();
+        builder.push_bytes(&bytes[2..]).unwrap();
+        assert_eq!(builder.finish().unwrap(), "né");
+    }
+
+    #[test]
+    fn utf8_builder_rejects_an_invalid_sequence() {
+        let mut builder = Utf8Builder::new();
+        let err = builder.push_bytes(b"ab\xFFcd").unwrap_err();
+        assert!(matches!(err, TextError::InvalidUtf8(e) if e.valid_up_to() == 2));
+    }
+
+    #[test]
+    fn utf8_builder_recovers_after_an_invalid_chunk() {
+        let mut builder = Utf8Builder::new();
+        assert!(builder.push_bytes(&[0xFF]).is_err());
+        builder.push_bytes(b"ok").unwrap();
+        assert_eq!(builder.finish().unwrap(), "ok");
+
+        let mut builder = Utf8Builder::new();
+        builder.push_bytes(b"ab\xC3").unwrap();
+        assert!(builder.push_bytes(b"x").is_err()); // C3 can't be followed by 'x'
+        builder.push_bytes(b"yz").unwrap();
+        assert_eq!(builder.finish().unwrap(), "abyz");
+    }
+
+    #[test]
+    fn utf8_builder_finish_reports_a_cut_off_char() {
+        let mut builder = Utf8Builder::new();
+        builder.push_bytes(b"a\xE2\x82").unwrap();
+        assert!(matches!(builder.finish(), Err(TextError::InvalidUtf8(_))));
+    }
+
+    #[test]
+    fn char_byte_ranges_of_multibyte_text() {
+        assert_eq!(char_byte_ranges("é").collect::<Vec<_>>(), [(0, 0..2)]);
+        assert_eq!(
+            char_byte_ranges("aé").collect::<Vec<_>>(),
+            [(0, 0..1), (1, 1..3)]
+        );
+        let s = "h🦀!";
+        for (_, range) in char_byte_ranges(s) {
+            assert_eq!(s[range].chars().count(), 1);
+        }
+    }
+
+    #[test]
+    fn char_windows_of_ascii() {
+        assert_eq!(
+            char_windows("abcd", 2).collect::<Vec<_>>(),
+            ["ab", "bc", "cd"]
+        );
+        assert_eq!(char_windows("abcd", 4).collect::<Vec<_>>(), ["abcd"]);
+    }
+
+    #[test]
+    fn char_windows_of_multibyte_text() {
+        assert_eq!(
+            char_windows("héllo", 3).collect::<Vec<_>>(),
+            ["hél", "éll", "llo"]
+        );
+        assert_eq!(char_windows("🦀é", 1).collect::<Vec<_>>(), ["🦀", "é"]);
+    }
+
+    #[test]
+    fn char_windows_of_size_zero_is_empty() {
+        assert_eq!(char_windows("abc", 0).count(), 0);
+    }
+
+    #[test]
+    fn char_windows_larger_than_the_text_is_empty() {
+        assert_eq!(char_windows("abc", 4).count(), 0);
+        assert_eq!(char_windows("", 1).count(), 0);
+    }
+
+    #[test]
+    fn is_char_boundary_range_inside_a_char() {
+        assert!(!is_char_boundary_range("héllo", 0..2)); // 2 is inside 'é'
+        assert!(!is_char_boundary_range("héllo", 2..4));
+    }
+
+    #[test]
+    fn is_char_boundary_range_on_boundaries() {
+        assert!(is_char_boundary_range("héllo", 0..3));
+        assert!(is_char_boundary_range("héllo", 1..1));
+        assert!(is_char_boundary_range("héllo", 3..6));
+    }
+
+    #[test]
+    fn is_char_boundary_range_past_the_end() {
+        assert!(!is_char_boundary_range("abc", 1..4));
+        assert!(!is_char_boundary_range("abc", 4..5));
+    }
+
+    #[test]
+    fn nth_char_offset_of_the_first_char_is_zero() {
+        assert_eq!(nth_char_offset("héllo", 0), Some(0));
+    }
+
+    #[test]
+    fn nth_char_offset_counts_multibyte_chars_by_their_bytes() {
+        assert_eq!(nth_char_offset("éab", 1), Some(2));
+        assert_eq!(nth_char_offset("éab", 2), Some(3));
+    }
+
+    #[test]
+    fn nth_char_offset_out_of_range_is_none() {
+        assert_eq!(nth_char_offset("éab", 3), None);
+        assert_eq!(nth_char_offset("", 0), None);
+    }
+
+    #[test]
+    fn truncate_to_bytes_backs_off_a_split_char() {
+        let mut s = String::from("café");
+        truncate_to_bytes(&mut s, 4); // 'é' is bytes 3..5
+        assert_eq!(s, "caf");
+    }
+
+    #[test]
+    fn truncate_to_bytes_to_zero_empties_the_string() {
+        let mut s = String::from("café");
+        truncate_to_bytes(&mut s, 0);
+        assert_eq!(s, "");
+    }
+
+    #[test]
+    fn truncate_to_bytes_past_the_end_changes_nothing() {
+        let mut s = String::from("café");
+        truncate_to_bytes(&mut s, 5);
+        assert_eq!(s, "café");
+        truncate_to_bytes(&mut s, 100);
+        assert_eq!(s, "café");
+    }
+
+    #[test]
+    fn ascii_stats_of_plain_ascii() {
+        assert_eq!(
+            ascii_stats("hello"),
+            AsciiStats {
+                ascii_bytes: 5,
+                multibyte_chars: 0,
+                max_char_bytes: 1,
+            }
+        );
+        assert_eq!(ascii_stats(""), AsciiStats::default());
+    }
+
+    #[test]
+    fn ascii_stats_of_two_and_four_byte_chars() {
+        assert_eq!(
+            ascii_stats("café"),
+            AsciiStats {
+                ascii_bytes: 3,
+                multibyte_chars: 1,
+                max_char_bytes: 2,
+            }
+        );
+        assert_eq!(
+            ascii_stats("é🦀!"),
+            AsciiStats {
+                ascii_bytes: 1,
+                multibyte_chars: 2,
+                max_char_bytes: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn strip_bom_removes_a_leading_mark() {
+        let text = String::from("\u{FEFF}hello");
+        let stripped = strip_bom(&text);
+        assert_eq!(stripped, "hello");
+        assert_eq!(stripped.as_ptr(), text[3..].as_ptr());
+    }
+
+    #[test]
+    fn strip_bom_removes_only_one_mark_and_only_at_the_start() {
+        assert_eq!(strip_bom("\u{FEFF}\u{FEFF}x"), "\u{FEFF}x");
+        assert_eq!(strip_bom("x\u{FEFF}"), "x\u{FEFF}");
+        assert_eq!(strip_bom("plain"), "plain");
+        assert_eq!(strip_bom(""), "");
+    }
+}
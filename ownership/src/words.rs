@@ -0,0 +1,402 @@
+/*
+
+Words
+
+A word is a run of non-whitespace characters. When the whole text is already in
+memory we can borrow it and hand out slices, but when the text comes from a reader
+(a file, a socket) we don't want to load all of it into one big String just to
+count what's inside.
+
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Read};
+
+/// Counts whitespace-delimited words read from `reader`.
+///
+/// The input is streamed through one small stack buffer that is reused for every
+/// read, so memory use stays the same no matter how large the input is. A word that
+/// is split across two reads is still counted once, because whether we are inside a
+/// word is carried over from one chunk to the next.
+///
+/// Words are split on Unicode whitespace, as [`str::split_whitespace`] does. When a
+/// read stops partway through a char, the up to 3 bytes of it that arrived are
+/// moved to the front of the buffer and finished by the next read. Input that isn't
+/// valid UTF-8, or that ends in the middle of a char, gives an
+/// [`ErrorKind::InvalidData`] error.
+pub fn count_words<R: Read>(mut reader: R) -> std::io::Result<usize> {
+    // one read's worth, plus room for the start of a char the last read cut off
+    let mut buf = [0u8; 1024 + 3];
+    let mut pending = 0;
+    let mut count = 0;
+    let mut in_word = false;
+
+    loop {
+        let n = match reader.read(&mut buf[pending..]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let filled = pending + n;
+
+        let valid = match std::str::from_utf8(&buf[..filled]) {
+            Ok(_) => filled,
+            // error_len() is None when the chunk just ends too early
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(std::io::Error::new(ErrorKind::InvalidData, e)),
+        };
+        // the prefix was just checked, so this can't fail
+        let text = std::str::from_utf8(&buf[..valid]).unwrap();
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                count += 1;
+            }
+        }
+
+        buf.copy_within(valid..filled, 0);
+        pending = filled - valid;
+    }
+
+    if pending > 0 {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "input ended in the middle of a char",
+        ));
+    }
+    Ok(count)
+}
+
+/*
+
+Comparing words
+
+Deriving PartialEq and Ord on a wrapper compares the inner String byte by byte, so
+"Apple" sorts before "banana" but after "Zebra". Writing the impls by hand lets the
+wrapper decide what "equal" and "less than" mean, here ignoring ASCII case.
+
+*/
+
+/// An owned word that compares and sorts without regard to ASCII case.
+#[derive(Debug, Clone)]
+pub struct Word(pub String);
+
+impl PartialEq for Word {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for Word {}
+
+impl PartialOrd for Word {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Word {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let a = self.0.bytes().map(|b| b.to_ascii_lowercase());
+        let b = other.0.bytes().map(|b| b.to_ascii_lowercase());
+        a.cmp(b)
+    }
+}
+
+/*
+
+Counting words
+
+The counts below are kept in a HashMap whose keys are &str slices of the input, so
+no word is ever copied. Because the keys borrow from s, the map (and the word we
+return from it) can't outlive s.
+
+*/
+
+// A key that hashes and compares without regard to ASCII case.
+struct Caseless<'a>(&'a str);
+
+impl PartialEq for Caseless<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Eq for Caseless<'_> {}
+
+impl Hash for Caseless<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+    }
+}
+
+fn most_frequent_by<'a, K: Hash + Eq>(
+    s: &'a str,
+    key: impl Fn(&'a str) -> K,
+) -> Option<(&'a str, usize)> {
+    // key -> (count, position of first appearance, first spelling seen)
+    let mut counts: HashMap<K, (usize, usize, &'a str)> = HashMap::new();
+    for (i, word) in s.split_whitespace().enumerate() {
+        counts.entry(key(word)).or_insert((0, i, word)).0 += 1;
+    }
+
+    counts
+        .into_values()
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+        .map(|(count, _, word)| (word, count))
+}
+
+/// Returns the word that appears most often in `s`, and how many times.
+///
+/// Words are compared exactly, so "The" and "the" are different words. On a tie the
+/// word that appeared first wins. The word borrows from `s`.
+pub fn most_frequent_word(s: &str) -> Option<(&str, usize)> {
+    most_frequent_by(s, |w| w)
+}
+
+/// Like [`most_frequent_word`], but ignoring ASCII case. The word returned is
+/// spelled the way it first appeared.
+pub fn most_frequent_word_ci(s: &str) -> Option<(&str, usize)> {
+    most_frequent_by(s, Caseless)
+}
+
+/// Counts the different words in `s`. Words are compared exactly.
+///
+/// The set only stores slices of `s`, so no word is copied.
+pub fn distinct_word_count(s: &str) -> usize {
+    s.split_whitespace().collect::<HashSet<&str>>().len()
+}
+
+/// Like [`distinct_word_count`], but "Word" and "WORD" count as the same word.
+pub fn distinct_word_count_ci(s: &str) -> usize {
+    s.split_whitespace()
+        .map(Caseless)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Counts how often each word occurs, in a map that owns copies of the words.
+///
+/// Use this when the counts have to outlive `s`, such as when they are stored or
+/// sent to another thread. Every distinct word costs one allocation.
+pub fn word_frequencies(s: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in s.split_whitespace() {
+        // only allocate a key the first time a word is seen
+        match counts.get_mut(word) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(word.to_string(), 1);
+            }
+        }
+    }
+    counts
+}
+
+/// Counts how often each word occurs, with keys borrowed from `s`.
+///
+/// Use this when the counts are only needed while `s` is still around; no word is
+/// copied, but the map can't outlive `s`.
+pub fn word_frequencies_ref(s: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for word in s.split_whitespace() {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Pairs each word of `s` with the word after it: "a b c" gives ("a", "b") and
+/// ("b", "c"). Both words of every pair borrow from `s`.
+pub fn bigrams(s: &str) -> impl Iterator<Item = (&str, &str)> {
+    s.split_whitespace().zip(s.split_whitespace().skip(1))
+}
+
+/// Returns the positions of `words` in sorted order, leaving `words` untouched.
+///
+/// Sorting a list of indices moves only usizes around; the &strs, and the text
+/// they point to, stay where they are. `words[i]` for each `i` in the result
+/// visits the words in byte order. The sort is stable, so equal words keep the
+/// order they came in.
+pub fn sorted_indices(words: &[&str]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..words.len()).collect();
+    indices.sort_by_key(|&i| words[i]);
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_words_over_a_cursor() {
+        let text = "the quick  brown\tfox\njumps";
+        assert_eq!(count_words(Cursor::new(text)).unwrap(), 5);
+    }
+
+    #[test]
+    fn count_words_of_an_empty_reader() {
+        assert_eq!(count_words(Cursor::new("")).unwrap(), 0);
+        assert_eq!(count_words(Cursor::new(" \n\t ")).unwrap(), 0);
+    }
+
+    #[test]
+    fn count_words_across_the_buffer_boundary() {
+        // "abcdefgh" starts at byte 1020, so it straddles the first 1024-byte read
+        let text = format!("{}abcdefgh x", " ".repeat(1020));
+        assert_eq!(count_words(Cursor::new(text)).unwrap(), 2);
+    }
+
+    #[test]
+    fn words_sort_ignoring_case() {
+        let mut words: Vec<Word> = ["banana", "Apple", "cherry"]
+            .iter()
+            .map(|w| Word(w.to_string()))
+            .collect();
+        words.sort();
+        let sorted: Vec<&str> = words.iter().map(|w| w.0.as_str()).collect();
+        assert_eq!(sorted, ["Apple", "banana", "cherry"]);
+    }
+
+    #[test]
+    fn words_equal_ignoring_case() {
+        assert_eq!(Word("Apple".to_string()), Word("APPLE".to_string()));
+        assert_ne!(Word("Apple".to_string()), Word("Apples".to_string()));
+    }
+
+    #[test]
+    fn most_frequent_word_of_nothing_is_none() {
+        assert_eq!(most_frequent_word(""), None);
+        assert_eq!(most_frequent_word("   "), None);
+    }
+
+    #[test]
+    fn most_frequent_word_finds_a_clear_winner() {
+        let text = String::from("b a b c b a");
+        let (word, count) = most_frequent_word(&text).unwrap();
+        assert_eq!((word, count), ("b", 3));
+        // the word is a slice of the input, not a copy
+        assert!(text.as_bytes().as_ptr_range().contains(&word.as_ptr()));
+    }
+
+    #[test]
+    fn most_frequent_word_tie_goes_to_the_earliest() {
+        assert_eq!(most_frequent_word("x y y x z"), Some(("x", 2)));
+        assert_eq!(most_frequent_word("y x x y z"), Some(("y", 2)));
+    }
+
+    #[test]
+    fn most_frequent_word_is_case_sensitive() {
+        assert_eq!(most_frequent_word("The the THE a a"), Some(("a", 2)));
+        assert_eq!(most_frequent_word_ci("The the THE a a"), Some(("The", 3)));
+    }
+
+    #[test]
+    fn distinct_word_count_counts_each_word_once() {
+        assert_eq!(distinct_word_count("a a b"), 2);
+        assert_eq!(distinct_word_count(""), 0);
+    }
+
+    #[test]
+    fn distinct_word_count_is_case_sensitive() {
+        assert_eq!(distinct_word_count("Word word WORD"), 3);
+        assert_eq!(distinct_word_count_ci("Word word WORD"), 1);
+        assert_eq!(distinct_word_count_ci("a A b"), 2);
+    }
+
+    #[test]
+    fn word_frequencies_owned_and_borrowed_agree() {
+        let text = String::from("to be or not to be");
+        let owned = word_frequencies(&text);
+        let borrowed = word_frequencies_ref(&text);
+
+        assert_eq!(owned.len(), 4);
+        assert_eq!(owned["to"], 2);
+        assert_eq!(owned["not"], 1);
+        for (word, count) in &borrowed {
+            assert_eq!(owned[*word], *count);
+        }
+        assert_eq!(owned.len(), borrowed.len());
+    }
+
+    #[test]
+    fn word_frequencies_ref_keys_point_into_the_input() {
+        let text = String::from("a b a");
+        let counts = word_frequencies_ref(&text);
+        let bytes = text.as_bytes().as_ptr_range();
+        for word in counts.keys() {
+            assert!(bytes.contains(&word.as_ptr()));
+        }
+        assert!(word_frequencies_ref("").is_empty());
+    }
+
+    #[test]
+    fn bigrams_of_too_few_words_are_empty() {
+        assert_eq!(bigrams("").count(), 0);
+        assert_eq!(bigrams("  alone ").count(), 0);
+    }
+
+    #[test]
+    fn bigrams_pair_each_word_with_the_next() {
+        let text = String::from("né à  Paris");
+        let pairs: Vec<(&str, &str)> = bigrams(&text).collect();
+        assert_eq!(pairs, [("né", "à"), ("à", "Paris")]);
+
+        let bytes = text.as_bytes().as_ptr_range();
+        for (a, b) in pairs {
+            assert!(bytes.contains(&a.as_ptr()));
+            assert!(bytes.contains(&b.as_ptr()));
+        }
+    }
+
+    #[test]
+    fn sorted_indices_orders_without_moving_the_words() {
+        let words = ["banana", "apple", "cherry"];
+        assert_eq!(sorted_indices(&words), [1, 0, 2]);
+        assert_eq!(words, ["banana", "apple", "cherry"]);
+    }
+
+    #[test]
+    fn sorted_indices_of_nothing() {
+        assert!(sorted_indices(&[]).is_empty());
+    }
+
+    #[test]
+    fn sorted_indices_keeps_equal_words_in_order() {
+        assert_eq!(sorted_indices(&["b", "a", "b", "a"]), [1, 3, 0, 2]);
+        // byte order puts uppercase first
+        assert_eq!(sorted_indices(&["a", "B"]), [1, 0]);
+    }
+
+    #[test]
+    fn count_words_splits_on_unicode_whitespace() {
+        assert_eq!(count_words(Cursor::new("a\u{3000}b")).unwrap(), 2);
+        assert_eq!(count_words(Cursor::new("né\u{a0}là\u{2003}")).unwrap(), 2);
+        assert_eq!(
+            count_words(Cursor::new("a\u{3000}b")).unwrap(),
+            "a\u{3000}b".split_whitespace().count()
+        );
+    }
+
+    #[test]
+    fn count_words_with_multibyte_whitespace_across_the_buffer_boundary() {
+        // the 3 bytes of U+3000 sit at 1022..1025, so the first read cuts it off
+        let text = format!("{}\u{3000}b", "a".repeat(1022));
+        assert_eq!(count_words(Cursor::new(text)).unwrap(), 2);
+    }
+
+    #[test]
+    fn count_words_rejects_input_that_is_not_utf8() {
+        let err = count_words(Cursor::new(b"ab \xFF cd")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = count_words(Cursor::new(b"ab \xE3\x80")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,10 @@
+// The borrow and move rules the module comments show are checked here: each file in
+// tests/ui must fail to compile with the error saved next to it in a .stderr file,
+// and each file in tests/ui/pass must build and run.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    t.pass("tests/ui/pass/*.rs");
+}
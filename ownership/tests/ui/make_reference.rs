@@ -0,0 +1,9 @@
+// returning a reference to a local: the String is dropped as the function returns
+fn make_reference() -> &'static String {
+    let s = String::from("hello");
+    &s
+}
+
+fn main() {
+    println!("{}", make_reference());
+}
@@ -0,0 +1,9 @@
+// with no input to borrow from, the returned reference has no lifetime to take
+fn make_reference() -> &String {
+    let s = String::from("hello");
+    &s
+}
+
+fn main() {
+    println!("{}", make_reference());
+}
@@ -0,0 +1,13 @@
+use ownership::moves::Record;
+
+fn main() {
+    let record = Record {
+        name: String::from("a"),
+        tags: Vec::new(),
+    };
+    let name = record.name; // moves just the name out
+    let tags = &record.tags; // fine, tags was not moved
+    println!("{name} {tags:?}");
+    let whole = record; // not fine, name is missing
+    println!("{whole:?}");
+}
@@ -0,0 +1,13 @@
+use ownership::{shared_borrow, unique_borrow};
+
+fn main() {
+    let mut s = String::from("hello");
+
+    let r1 = shared_borrow!(s);
+    let r2 = shared_borrow!(s); // shared borrows can overlap
+    assert_eq!(format!("{r1} {r2}"), "hello hello");
+
+    let r3 = unique_borrow!(s); // r1 and r2 are no longer used
+    r3.push_str(", world");
+    assert_eq!(s, "hello, world");
+}
@@ -0,0 +1,15 @@
+use ownership::with_borrows;
+
+fn main() {
+    let mut s = String::from("hello");
+
+    let joined = with_borrows!(s => shared r1, r2; {
+        format!("{r1} {r2}")
+    });
+    assert_eq!(joined, "hello hello");
+
+    with_borrows!(s => unique r; {
+        r.push_str(", world");
+    });
+    assert_eq!(s, "hello, world");
+}
@@ -0,0 +1,8 @@
+use ownership::closures::spawn_with_owned;
+
+fn main() {
+    let data = String::from("hello");
+    let handle = spawn_with_owned(data);
+    println!("{data}");
+    handle.join().unwrap();
+}
@@ -0,0 +1,9 @@
+use ownership::{shared_borrow, unique_borrow};
+
+fn main() {
+    let mut s = String::from("hello");
+
+    let r1 = shared_borrow!(s);
+    let r2 = unique_borrow!(s); // r1 is still used below
+    println!("{r1} {r2}");
+}
@@ -0,0 +1,9 @@
+use ownership::with_borrows;
+
+fn main() {
+    let mut s = String::from("hello");
+    with_borrows!(s => shared r; {
+        s.push('!');
+        println!("{r}");
+    });
+}
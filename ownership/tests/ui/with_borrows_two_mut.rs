@@ -0,0 +1,10 @@
+use ownership::with_borrows;
+
+fn main() {
+    let mut s = String::from("hello");
+    with_borrows!(s => unique a; {
+        let b = &mut s;
+        a.push('x');
+        b.push('y');
+    });
+}
@@ -0,0 +1,6 @@
+use ownership::with_borrows;
+
+fn main() {
+    let mut s = String::from("hello");
+    with_borrows!(s => unique a, b; {});
+}